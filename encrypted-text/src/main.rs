@@ -9,8 +9,27 @@ use rsa::{
     RsaPublicKey,
     RsaPrivateKey,
 };
-use pkcs8::{DecodePublicKey, DecodePrivateKey};
-use std::{fs, path::PathBuf};
+use pkcs8::{DecodePublicKey, DecodePrivateKey, EncodePublicKey};
+use p256::{ecdh::diffie_hellman, PublicKey as EcPublicKey, SecretKey as EcSecretKey};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use cbc::cipher::{BlockDecryptMut, BlockEncryptMut};
+use cbc::cipher::block_padding::Pkcs7;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use aes_gcm::aead::Payload;
+use std::{
+    fs,
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+type Aes256Ctr = ctr::Ctr128BE<aes::Aes256>;
+type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+type HmacSha256 = Hmac<Sha256>;
 
 #[derive(Debug)]
 enum AppError {
@@ -19,6 +38,9 @@ enum AppError {
     Rsa(rsa::Error),
     Pkcs8(pkcs8::Error),
     Base64(base64::DecodeError),
+    Ecdh(String),
+    Kdf(String),
+    EncryptedKey(String),
     Other(String),
 }
 
@@ -30,6 +52,9 @@ impl std::fmt::Display for AppError {
             AppError::Rsa(err) => write!(f, "RSA error: {}", err),
             AppError::Pkcs8(err) => write!(f, "PKCS8 error: {}", err),
             AppError::Base64(err) => write!(f, "Base64 decode error: {}", err),
+            AppError::Ecdh(err) => write!(f, "ECDH error: {}", err),
+            AppError::Kdf(err) => write!(f, "KDF error: {}", err),
+            AppError::EncryptedKey(err) => write!(f, "Encrypted private key error: {}", err),
             AppError::Other(err) => write!(f, "Other error: {}", err),
         }
     }
@@ -43,6 +68,9 @@ impl std::error::Error for AppError {
             AppError::Rsa(err) => Some(err),
             AppError::Pkcs8(err) => Some(err),
             AppError::Base64(err) => Some(err),
+            AppError::Ecdh(_) => None,
+            AppError::Kdf(_) => None,
+            AppError::EncryptedKey(_) => None,
             AppError::Other(_) => None,
         }
     }
@@ -91,28 +119,115 @@ fn encrypt_asymmetric(to_encrypt: &[u8], public_key_path: &PathBuf) -> Result<St
     Ok(general_purpose::STANDARD.encode(&encrypted))
 }
 
+/// Which cipher a versioned envelope uses. GCM is the authenticated default;
+/// CBC and CTR are offered for interoperability with systems that don't speak
+/// GCM, at the cost of built-in integrity checking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CipherAlgorithm {
+    Aes256Gcm,
+    Aes256Cbc,
+    Aes128Ctr,
+    Aes256Ctr,
+}
+
+impl CipherAlgorithm {
+    const ENVELOPE_VERSION: &'static str = "v1";
+
+    fn tag(self) -> &'static str {
+        match self {
+            CipherAlgorithm::Aes256Gcm => "aes256gcm",
+            CipherAlgorithm::Aes256Cbc => "aes256cbc",
+            CipherAlgorithm::Aes128Ctr => "aes128ctr",
+            CipherAlgorithm::Aes256Ctr => "aes256ctr",
+        }
+    }
+
+    fn from_tag(tag: &str) -> Result<Self, AppError> {
+        match tag {
+            "aes256gcm" => Ok(CipherAlgorithm::Aes256Gcm),
+            "aes256cbc" => Ok(CipherAlgorithm::Aes256Cbc),
+            "aes128ctr" => Ok(CipherAlgorithm::Aes128Ctr),
+            "aes256ctr" => Ok(CipherAlgorithm::Aes256Ctr),
+            other => Err(AppError::Other(format!("Unknown cipher algorithm tag: {}", other))),
+        }
+    }
+
+    fn key_len(self) -> usize {
+        match self {
+            CipherAlgorithm::Aes128Ctr => 16,
+            _ => 32,
+        }
+    }
+
+    fn is_authenticated(self) -> bool {
+        matches!(self, CipherAlgorithm::Aes256Gcm)
+    }
+}
+
 /// Symmetric encryption function (part of hybrid encryption)
+/// Produces a versioned, self-describing envelope
+/// (`v1:algorithm:encrypted_key:iv_or_nonce:ciphertext`) so `decrypt_symmetric`
+/// can pick the right cipher without the caller having to know it in advance.
 /// text: Plaintext to be encrypted
 /// public_key_path: Path to the public key file used to encrypt the symmetric key
-fn encrypt_symmetric(text: &str, public_key_path: &PathBuf) -> Result<String, AppError> {
-    let mut key_bytes = [0u8; 32];
-    OsRng.fill_bytes(&mut key_bytes);
-
-    let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
-    let cipher = Aes256Gcm::new(key);
+/// algorithm: Which cipher to seal `text` with; GCM is authenticated, CBC/CTR are not
+fn encrypt_symmetric(text: &str, public_key_path: &PathBuf, algorithm: CipherAlgorithm) -> Result<String, AppError> {
+    if !algorithm.is_authenticated() {
+        eprintln!(
+            "Warning: {} provides no built-in authentication; ciphertext integrity is not checked on decrypt.",
+            algorithm.tag()
+        );
+    }
 
-    let mut nonce_bytes = [0u8; 12];
-    OsRng.fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
+    let mut key_bytes = vec![0u8; algorithm.key_len()];
+    OsRng.fill_bytes(&mut key_bytes);
 
-    let ciphertext = cipher.encrypt(nonce, text.as_bytes())?;
+    let (iv_bytes, ciphertext): (Vec<u8>, Vec<u8>) = match algorithm {
+        CipherAlgorithm::Aes256Gcm => {
+            let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+            let cipher = Aes256Gcm::new(key);
+            let mut nonce_bytes = [0u8; 12];
+            OsRng.fill_bytes(&mut nonce_bytes);
+            let nonce = Nonce::from_slice(&nonce_bytes);
+            let ciphertext = cipher.encrypt(nonce, text.as_bytes())?;
+            (nonce_bytes.to_vec(), ciphertext)
+        }
+        CipherAlgorithm::Aes256Cbc => {
+            let mut iv_bytes = [0u8; 16];
+            OsRng.fill_bytes(&mut iv_bytes);
+            let encryptor = Aes256CbcEnc::new_from_slices(&key_bytes, &iv_bytes)
+                .map_err(|e| AppError::Other(e.to_string()))?;
+            let ciphertext = encryptor.encrypt_padded_vec_mut::<Pkcs7>(text.as_bytes());
+            (iv_bytes.to_vec(), ciphertext)
+        }
+        CipherAlgorithm::Aes128Ctr => {
+            let mut iv_bytes = [0u8; 16];
+            OsRng.fill_bytes(&mut iv_bytes);
+            let mut ciphertext = text.as_bytes().to_vec();
+            let mut cipher = Aes128Ctr::new_from_slices(&key_bytes, &iv_bytes)
+                .map_err(|e| AppError::Other(e.to_string()))?;
+            cipher.apply_keystream(&mut ciphertext);
+            (iv_bytes.to_vec(), ciphertext)
+        }
+        CipherAlgorithm::Aes256Ctr => {
+            let mut iv_bytes = [0u8; 16];
+            OsRng.fill_bytes(&mut iv_bytes);
+            let mut ciphertext = text.as_bytes().to_vec();
+            let mut cipher = Aes256Ctr::new_from_slices(&key_bytes, &iv_bytes)
+                .map_err(|e| AppError::Other(e.to_string()))?;
+            cipher.apply_keystream(&mut ciphertext);
+            (iv_bytes.to_vec(), ciphertext)
+        }
+    };
 
     let encrypted_symmetric_key = encrypt_asymmetric(&key_bytes, public_key_path)?;
 
     Ok(format!(
-        "{}:{}:{}",
+        "{}:{}:{}:{}:{}",
+        CipherAlgorithm::ENVELOPE_VERSION,
+        algorithm.tag(),
         encrypted_symmetric_key,
-        general_purpose::STANDARD.encode(&nonce_bytes),
+        general_purpose::STANDARD.encode(&iv_bytes),
         general_purpose::STANDARD.encode(&ciphertext)
     ))
 }
@@ -120,10 +235,23 @@ fn encrypt_symmetric(text: &str, public_key_path: &PathBuf) -> Result<String, Ap
 /// Asymmetric decryption function
 /// to_decrypt_base64: Base64 encoded symmetric key ciphertext
 /// private_key_path: Path to the private key file
-fn decrypt_asymmetric(to_decrypt_base64: &str, private_key_path: &PathBuf) -> Result<Vec<u8>, AppError> {
+/// passphrase: If the key is a passphrase-protected (PBES2) PKCS#8 key, the
+/// passphrase to unwrap it with; `None` for a plain, unencrypted PKCS#8 key.
+fn decrypt_asymmetric(
+    to_decrypt_base64: &str,
+    private_key_path: &PathBuf,
+    passphrase: Option<&str>,
+) -> Result<Vec<u8>, AppError> {
     let private_key_pem = fs::read_to_string(private_key_path)?;
-    let private_key = RsaPrivateKey::from_pkcs8_pem(&private_key_pem)
-        .map_err(|e| AppError::Pkcs8(e.into()))?;
+    let private_key = match passphrase {
+        Some(passphrase) => RsaPrivateKey::from_pkcs8_encrypted_pem(&private_key_pem, passphrase).map_err(|e| {
+            AppError::EncryptedKey(format!(
+                "Failed to decrypt PBES2-protected PKCS#8 key (check the passphrase): {}",
+                e
+            ))
+        })?,
+        None => RsaPrivateKey::from_pkcs8_pem(&private_key_pem).map_err(|e| AppError::Pkcs8(e.into()))?,
+    };
 
     let encrypted_bytes = general_purpose::STANDARD.decode(to_decrypt_base64)?;
 
@@ -133,41 +261,462 @@ fn decrypt_asymmetric(to_decrypt_base64: &str, private_key_path: &PathBuf) -> Re
 }
 
 /// Symmetric decryption function (part of hybrid decryption)
-/// encrypted_string: Full encrypted string (format: encrypted_key:Nonce:ciphertext)
+/// Parses the versioned envelope's algorithm tag first, then dispatches to
+/// the matching cipher, so callers never need to know ahead of time which
+/// mode `encrypt_symmetric` used.
+/// encrypted_string: Full encrypted string (format: v1:algorithm:encrypted_key:iv_or_nonce:ciphertext)
 /// private_key_path: Path to the private key file used to decrypt the symmetric key
-fn decrypt_symmetric(encrypted_string: &str, private_key_path: &PathBuf) -> Result<String, AppError> {
-    let parts: Vec<&str> = encrypted_string.split(':').collect();
-    if parts.len() != 3 {
+/// passphrase: Passphrase for a PBES2-protected private key, or `None` if it's unencrypted
+fn decrypt_symmetric(
+    encrypted_string: &str,
+    private_key_path: &PathBuf,
+    passphrase: Option<&str>,
+) -> Result<String, AppError> {
+    let parts: Vec<&str> = encrypted_string.splitn(5, ':').collect();
+    if parts.len() != 5 || parts[0] != CipherAlgorithm::ENVELOPE_VERSION {
         return Err(AppError::Other("Invalid encrypted string format".to_string()));
     }
 
-    let encrypted_symmetric_key_base64 = parts[0];
-    let nonce_base64 = parts[1];
-    let ciphertext_base64 = parts[2];
+    let algorithm = CipherAlgorithm::from_tag(parts[1])?;
+    if !algorithm.is_authenticated() {
+        eprintln!(
+            "Warning: {} provides no built-in authentication; ciphertext integrity was not checked.",
+            algorithm.tag()
+        );
+    }
+
+    let encrypted_symmetric_key_base64 = parts[2];
+    let iv_base64 = parts[3];
+    let ciphertext_base64 = parts[4];
 
     // 1. Decrypt the symmetric key using asymmetric decryption
-    let decrypted_symmetric_key_bytes = decrypt_asymmetric(encrypted_symmetric_key_base64, private_key_path)?;
-    let key = Key::<Aes256Gcm>::from_slice(&decrypted_symmetric_key_bytes);
-    let cipher = Aes256Gcm::new(key);
+    let key_bytes = decrypt_asymmetric(encrypted_symmetric_key_base64, private_key_path, passphrase)?;
 
-    // 2. Decode Nonce and ciphertext
-    let nonce_bytes = general_purpose::STANDARD.decode(nonce_base64)?;
-    let nonce = Nonce::from_slice(&nonce_bytes);
+    // 2. Decode IV/nonce and ciphertext
+    let iv_bytes = general_purpose::STANDARD.decode(iv_base64)?;
     let ciphertext = general_purpose::STANDARD.decode(ciphertext_base64)?;
 
-    // 3. Decrypt the ciphertext
-    let decrypted_text_bytes = cipher.decrypt(nonce, ciphertext.as_ref())?;
+    // 3. Decrypt the ciphertext with the algorithm recorded in the envelope
+    let decrypted_text_bytes = match algorithm {
+        CipherAlgorithm::Aes256Gcm => {
+            let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+            let cipher = Aes256Gcm::new(key);
+            let nonce = Nonce::from_slice(&iv_bytes);
+            cipher.decrypt(nonce, ciphertext.as_ref())?
+        }
+        CipherAlgorithm::Aes256Cbc => {
+            let decryptor = Aes256CbcDec::new_from_slices(&key_bytes, &iv_bytes)
+                .map_err(|e| AppError::Other(e.to_string()))?;
+            decryptor
+                .decrypt_padded_vec_mut::<Pkcs7>(&ciphertext)
+                .map_err(|e| AppError::Other(format!("CBC padding error: {}", e)))?
+        }
+        CipherAlgorithm::Aes128Ctr => {
+            let mut plaintext = ciphertext;
+            let mut cipher = Aes128Ctr::new_from_slices(&key_bytes, &iv_bytes)
+                .map_err(|e| AppError::Other(e.to_string()))?;
+            cipher.apply_keystream(&mut plaintext);
+            plaintext
+        }
+        CipherAlgorithm::Aes256Ctr => {
+            let mut plaintext = ciphertext;
+            let mut cipher = Aes256Ctr::new_from_slices(&key_bytes, &iv_bytes)
+                .map_err(|e| AppError::Other(e.to_string()))?;
+            cipher.apply_keystream(&mut plaintext);
+            plaintext
+        }
+    };
+
     Ok(String::from_utf8(decrypted_text_bytes)
         .map_err(|e| AppError::Other(format!("UTF-8 decode error: {}", e)))?)
 }
 
+/// Chunk size used by `encrypt_stream`/`decrypt_stream`. Large enough to amortize
+/// per-chunk overhead, small enough that a chunk is always sealed in memory.
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Magic bytes identifying the streaming envelope format produced by `encrypt_stream`.
+const STREAM_MAGIC: &[u8; 4] = b"STR1";
+
+/// Reads into `buffer` until it's full or the reader is exhausted, unlike a single
+/// `Read::read` call which may return fewer bytes than requested.
+fn fill_buffer<R: Read>(reader: &mut R, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Binds the chunk's position and whether it's the final chunk into the AEAD's
+/// additional data, so truncating or reordering chunks is caught as an
+/// authentication failure on decrypt.
+fn stream_chunk_aad(chunk_index: u64, is_last: bool) -> [u8; 9] {
+    let mut aad = [0u8; 9];
+    aad[..8].copy_from_slice(&chunk_index.to_be_bytes());
+    aad[8] = is_last as u8;
+    aad
+}
+
+/// Derives the per-chunk nonce by XORing the chunk counter into the low 8 bytes
+/// of the random base nonce, so every chunk is sealed under a unique nonce.
+fn stream_chunk_nonce(base_nonce: &[u8; 12], chunk_index: u64) -> [u8; 12] {
+    let mut nonce = *base_nonce;
+    let counter_bytes = chunk_index.to_be_bytes();
+    for i in 0..8 {
+        nonce[4 + i] ^= counter_bytes[i];
+    }
+    nonce
+}
+
+/// Streaming counterpart to `encrypt_symmetric`: encrypts from `reader` to
+/// `writer` in fixed-size chunks, each sealed independently with AES-256-GCM,
+/// so large files never need to be held in memory at once. Writes a
+/// length-prefixed header once (magic, chunk size, base nonce, RSA-wrapped key),
+/// then one `is_last flag | length | ciphertext+tag` record per chunk.
+fn encrypt_stream<R: Read, W: Write>(mut reader: R, mut writer: W, public_key_path: &PathBuf) -> Result<(), AppError> {
+    let mut key_bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut key_bytes);
+    let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+    let cipher = Aes256Gcm::new(key);
+
+    let mut base_nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut base_nonce);
+
+    let wrapped_key = encrypt_asymmetric(&key_bytes, public_key_path)?.into_bytes();
+
+    writer.write_all(STREAM_MAGIC)?;
+    writer.write_all(&(STREAM_CHUNK_SIZE as u32).to_be_bytes())?;
+    writer.write_all(&base_nonce)?;
+    writer.write_all(&(wrapped_key.len() as u32).to_be_bytes())?;
+    writer.write_all(&wrapped_key)?;
+
+    let mut buffer = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut filled = fill_buffer(&mut reader, &mut buffer)?;
+    let mut chunk_index: u64 = 0;
+
+    loop {
+        // Peek one byte past the chunk we just filled to find out, without
+        // buffering the whole file, whether this chunk is the last one.
+        let mut probe = [0u8; 1];
+        let is_last = reader.read(&mut probe)? == 0;
+
+        let aad = stream_chunk_aad(chunk_index, is_last);
+        let nonce_bytes = stream_chunk_nonce(&base_nonce, chunk_index);
+        let nonce = Nonce::from_slice(&nonce_bytes);
+        let ciphertext = cipher.encrypt(nonce, Payload { msg: &buffer[..filled], aad: &aad })?;
+
+        writer.write_all(&[is_last as u8])?;
+        writer.write_all(&(ciphertext.len() as u32).to_be_bytes())?;
+        writer.write_all(&ciphertext)?;
+
+        if is_last {
+            break;
+        }
+
+        buffer[0] = probe[0];
+        filled = 1 + fill_buffer(&mut reader, &mut buffer[1..])?;
+        chunk_index += 1;
+    }
+
+    Ok(())
+}
+
+/// Streaming counterpart to `decrypt_symmetric`: reverses `encrypt_stream`,
+/// opening each chunk's AEAD seal as it's read so `writer` never needs the
+/// whole plaintext in memory either.
+fn decrypt_stream<R: Read, W: Write>(mut reader: R, mut writer: W, private_key_path: &PathBuf) -> Result<(), AppError> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != STREAM_MAGIC {
+        return Err(AppError::Other("Invalid stream header magic".to_string()));
+    }
+
+    let mut chunk_size_bytes = [0u8; 4];
+    reader.read_exact(&mut chunk_size_bytes)?;
+
+    let mut base_nonce = [0u8; 12];
+    reader.read_exact(&mut base_nonce)?;
+
+    let mut wrapped_key_len_bytes = [0u8; 4];
+    reader.read_exact(&mut wrapped_key_len_bytes)?;
+    let wrapped_key_len = u32::from_be_bytes(wrapped_key_len_bytes) as usize;
+    let mut wrapped_key = vec![0u8; wrapped_key_len];
+    reader.read_exact(&mut wrapped_key)?;
+    let wrapped_key = String::from_utf8(wrapped_key)
+        .map_err(|e| AppError::Other(format!("Invalid wrapped key encoding: {}", e)))?;
+
+    let key_bytes = decrypt_asymmetric(&wrapped_key, private_key_path, None)?;
+    let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+    let cipher = Aes256Gcm::new(key);
+
+    let mut chunk_index: u64 = 0;
+    loop {
+        let mut is_last_byte = [0u8; 1];
+        reader.read_exact(&mut is_last_byte)?;
+        let is_last = is_last_byte[0] != 0;
+
+        let mut len_bytes = [0u8; 4];
+        reader.read_exact(&mut len_bytes)?;
+        let len = u32::from_be_bytes(len_bytes) as usize;
+        let mut ciphertext = vec![0u8; len];
+        reader.read_exact(&mut ciphertext)?;
+
+        let aad = stream_chunk_aad(chunk_index, is_last);
+        let nonce_bytes = stream_chunk_nonce(&base_nonce, chunk_index);
+        let nonce = Nonce::from_slice(&nonce_bytes);
+        let plaintext = cipher.decrypt(nonce, Payload { msg: &ciphertext, aad: &aad }).map_err(|_| {
+            AppError::Other(format!(
+                "Chunk {} failed authentication (stream truncated, reordered, or tampered with)",
+                chunk_index
+            ))
+        })?;
+        writer.write_all(&plaintext)?;
+
+        if is_last {
+            break;
+        }
+        chunk_index += 1;
+    }
+
+    Ok(())
+}
+
+/// ECIES hybrid encryption function
+/// Mirrors `encrypt_symmetric`, but wraps the AES key via an EC (P-256) key agreement
+/// instead of RSA, so recipients only need a much smaller EC keypair.
+/// text: Plaintext to be encrypted
+/// recipient_public_key_path: Path to the recipient's EC public key file (SPKI PEM)
+fn encrypt_ecies(text: &str, recipient_public_key_path: &PathBuf) -> Result<String, AppError> {
+    let public_key_pem = fs::read_to_string(recipient_public_key_path)?;
+    let recipient_public_key = EcPublicKey::from_public_key_pem(&public_key_pem)
+        .map_err(|e| AppError::Pkcs8(e.into()))?;
+
+    let ephemeral_secret = EcSecretKey::random(&mut OsRng);
+    let shared_secret = diffie_hellman(
+        ephemeral_secret.to_nonzero_scalar(),
+        recipient_public_key.as_affine(),
+    );
+
+    let mut key_bytes = [0u8; 32];
+    Hkdf::<Sha256>::new(None, shared_secret.raw_secret_bytes().as_slice())
+        .expand(b"ecies-aes256gcm", &mut key_bytes)
+        .map_err(|e| AppError::Kdf(e.to_string()))?;
+
+    let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+    let cipher = Aes256Gcm::new(key);
+
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher.encrypt(nonce, text.as_bytes())?;
+
+    let ephemeral_public_key_der = ephemeral_secret
+        .public_key()
+        .to_public_key_der()
+        .map_err(|e| AppError::Pkcs8(e.into()))?;
+
+    Ok(format!(
+        "{}:{}:{}",
+        general_purpose::STANDARD.encode(ephemeral_public_key_der.as_bytes()),
+        general_purpose::STANDARD.encode(&nonce_bytes),
+        general_purpose::STANDARD.encode(&ciphertext)
+    ))
+}
+
+/// ECIES hybrid decryption function
+/// encrypted_string: Full encrypted string (format: ephemeral_pubkey:nonce:ciphertext+tag)
+/// private_key_path: Path to the recipient's EC private key file (PKCS#8 PEM)
+fn decrypt_ecies(encrypted_string: &str, private_key_path: &PathBuf) -> Result<String, AppError> {
+    let parts: Vec<&str> = encrypted_string.split(':').collect();
+    if parts.len() != 3 {
+        return Err(AppError::Other("Invalid ECIES string format".to_string()));
+    }
+
+    let private_key_pem = fs::read_to_string(private_key_path)?;
+    let recipient_secret_key = EcSecretKey::from_pkcs8_pem(&private_key_pem)
+        .map_err(|e| AppError::Pkcs8(e.into()))?;
+
+    let ephemeral_public_key_der = general_purpose::STANDARD.decode(parts[0])?;
+    let ephemeral_public_key = EcPublicKey::from_public_key_der(&ephemeral_public_key_der)
+        .map_err(|e| AppError::Ecdh(e.to_string()))?;
+
+    let shared_secret = diffie_hellman(
+        recipient_secret_key.to_nonzero_scalar(),
+        ephemeral_public_key.as_affine(),
+    );
+
+    let mut key_bytes = [0u8; 32];
+    Hkdf::<Sha256>::new(None, shared_secret.raw_secret_bytes().as_slice())
+        .expand(b"ecies-aes256gcm", &mut key_bytes)
+        .map_err(|e| AppError::Kdf(e.to_string()))?;
+
+    let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+    let cipher = Aes256Gcm::new(key);
+
+    let nonce_bytes = general_purpose::STANDARD.decode(parts[1])?;
+    let nonce = Nonce::from_slice(&nonce_bytes);
+    let ciphertext = general_purpose::STANDARD.decode(parts[2])?;
+
+    let decrypted = cipher.decrypt(nonce, ciphertext.as_ref())?;
+    String::from_utf8(decrypted).map_err(|e| AppError::Other(format!("UTF-8 decode error: {}", e)))
+}
+
+const SCRYPT_LOG_N_DEFAULT: u8 = 14; // N = 16384
+const SCRYPT_R_DEFAULT: u32 = 8;
+const SCRYPT_P_DEFAULT: u32 = 1;
+const PBKDF2_ITERATIONS_DEFAULT: u32 = 10240;
+
+/// Which KDF a passphrase-derived keystore uses to stretch the passphrase into a key.
+#[derive(Debug, Clone, Copy)]
+enum KdfKind {
+    Scrypt,
+    Pbkdf2,
+}
+
+/// The KDF name and tunable parameters, recorded in the keystore so decryption
+/// can reproduce the exact same derived key.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kdf", rename_all = "lowercase")]
+enum KdfParams {
+    Scrypt { n: u32, r: u32, p: u32 },
+    Pbkdf2 { iterations: u32 },
+}
+
+/// A self-describing, passphrase-protected envelope: no RSA/EC keypair required.
+/// Modeled on the Ethereum keystore format (KDF params + salt + AES-128-CTR
+/// ciphertext + an HMAC-SHA256 MAC, keyed with the second half of the derived
+/// key, over the cipher name, the nonce and the ciphertext).
+#[derive(Debug, Serialize, Deserialize)]
+struct Keystore {
+    #[serde(flatten)]
+    kdf: KdfParams,
+    salt: String,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+    mac: String,
+}
+
+fn derive_keystore_key(passphrase: &str, salt: &[u8], kdf_kind: KdfKind) -> Result<([u8; 32], KdfParams), AppError> {
+    match kdf_kind {
+        KdfKind::Scrypt => {
+            let params = scrypt::Params::new(SCRYPT_LOG_N_DEFAULT, SCRYPT_R_DEFAULT, SCRYPT_P_DEFAULT, 32)
+                .map_err(|e| AppError::Kdf(e.to_string()))?;
+            let mut derived_key = [0u8; 32];
+            scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut derived_key)
+                .map_err(|e| AppError::Kdf(e.to_string()))?;
+            Ok((
+                derived_key,
+                KdfParams::Scrypt { n: 1 << SCRYPT_LOG_N_DEFAULT, r: SCRYPT_R_DEFAULT, p: SCRYPT_P_DEFAULT },
+            ))
+        }
+        KdfKind::Pbkdf2 => {
+            let mut derived_key = [0u8; 32];
+            pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ITERATIONS_DEFAULT, &mut derived_key);
+            Ok((derived_key, KdfParams::Pbkdf2 { iterations: PBKDF2_ITERATIONS_DEFAULT }))
+        }
+    }
+}
+
+fn rederive_keystore_key(passphrase: &str, salt: &[u8], kdf_params: &KdfParams) -> Result<[u8; 32], AppError> {
+    match *kdf_params {
+        KdfParams::Scrypt { n, r, p } => {
+            let log_n = (n as f64).log2() as u8;
+            let params = scrypt::Params::new(log_n, r, p, 32).map_err(|e| AppError::Kdf(e.to_string()))?;
+            let mut derived_key = [0u8; 32];
+            scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut derived_key)
+                .map_err(|e| AppError::Kdf(e.to_string()))?;
+            Ok(derived_key)
+        }
+        KdfParams::Pbkdf2 { iterations } => {
+            let mut derived_key = [0u8; 32];
+            pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut derived_key);
+            Ok(derived_key)
+        }
+    }
+}
+
+/// Authenticates the fields an attacker could tamper with independently of
+/// the derived key: `cipher` and `nonce` (salt/KDF params are only covered
+/// indirectly, via their effect on `derived_key`).
+fn compute_keystore_mac(derived_key: &[u8; 32], cipher: &str, nonce: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(&derived_key[16..32]).expect("HMAC accepts any key length");
+    mac.update(cipher.as_bytes());
+    mac.update(nonce);
+    mac.update(ciphertext);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Encrypts `text` using a key derived purely from `passphrase`, so no RSA/EC
+/// key file is needed. Returns the keystore as a JSON string.
+fn encrypt_with_passphrase(text: &str, passphrase: &str, kdf_kind: KdfKind) -> Result<String, AppError> {
+    let mut salt = [0u8; 32];
+    OsRng.fill_bytes(&mut salt);
+    let (derived_key, kdf_params) = derive_keystore_key(passphrase, &salt, kdf_kind)?;
+
+    let mut iv = [0u8; 16];
+    OsRng.fill_bytes(&mut iv);
+
+    let mut ciphertext = text.as_bytes().to_vec();
+    let mut cipher = Aes128Ctr::new_from_slices(&derived_key[..16], &iv)
+        .map_err(|e| AppError::Kdf(e.to_string()))?;
+    cipher.apply_keystream(&mut ciphertext);
+
+    let cipher_name = "aes-128-ctr";
+    let mac = compute_keystore_mac(&derived_key, cipher_name, &iv, &ciphertext);
+
+    let keystore = Keystore {
+        kdf: kdf_params,
+        salt: general_purpose::STANDARD.encode(salt),
+        cipher: cipher_name.to_string(),
+        nonce: general_purpose::STANDARD.encode(iv),
+        ciphertext: general_purpose::STANDARD.encode(&ciphertext),
+        mac: general_purpose::STANDARD.encode(mac),
+    };
+
+    serde_json::to_string(&keystore).map_err(|e| AppError::Other(format!("Keystore serialization error: {}", e)))
+}
+
+/// Decrypts a keystore produced by `encrypt_with_passphrase`. Re-runs the
+/// recorded KDF, verifies the MAC in constant time, and only then decrypts.
+fn decrypt_with_passphrase(keystore_json: &str, passphrase: &str) -> Result<String, AppError> {
+    let keystore: Keystore = serde_json::from_str(keystore_json)
+        .map_err(|e| AppError::Other(format!("Keystore deserialization error: {}", e)))?;
+
+    let salt = general_purpose::STANDARD.decode(&keystore.salt)?;
+    let derived_key = rederive_keystore_key(passphrase, &salt, &keystore.kdf)?;
+
+    let ciphertext = general_purpose::STANDARD.decode(&keystore.ciphertext)?;
+    let expected_mac = general_purpose::STANDARD.decode(&keystore.mac)?;
+    let iv = general_purpose::STANDARD.decode(&keystore.nonce)?;
+
+    let mut mac = HmacSha256::new_from_slice(&derived_key[16..32]).expect("HMAC accepts any key length");
+    mac.update(keystore.cipher.as_bytes());
+    mac.update(&iv);
+    mac.update(&ciphertext);
+    mac.verify_slice(&expected_mac)
+        .map_err(|_| AppError::Other("Keystore MAC mismatch: wrong passphrase or corrupted keystore".to_string()))?;
+
+    let mut plaintext = ciphertext;
+    let mut cipher = Aes128Ctr::new_from_slices(&derived_key[..16], &iv)
+        .map_err(|e| AppError::Kdf(e.to_string()))?;
+    cipher.apply_keystream(&mut plaintext);
+
+    String::from_utf8(plaintext).map_err(|e| AppError::Other(format!("UTF-8 decode error: {}", e)))
+}
+
 
 // Extracts the main logic for easier testing
 fn run_encryption_example(public_key_path: &PathBuf) -> Result<String, AppError> {
     let plaintext = "This is a secret message that I hope will be securely encrypted.";
     println!("Original plaintext: {}", plaintext);
 
-    let encrypted_string = encrypt_symmetric(plaintext, public_key_path)?;
+    let encrypted_string = encrypt_symmetric(plaintext, public_key_path, CipherAlgorithm::Aes256Gcm)?;
     println!("Encrypted string: {}", encrypted_string);
     Ok(encrypted_string)
 }
@@ -190,7 +739,7 @@ fn main() -> Result<(), AppError> {
 
     // --- Add decryption example to use decrypt_symmetric and decrypt_asymmetric functions ---
     println!("\nStarting decryption...");
-    match decrypt_symmetric(&encrypted_text, &private_key_path) {
+    match decrypt_symmetric(&encrypted_text, &private_key_path, None) {
         Ok(decrypted_string) => {
             println!("Decrypted plaintext: {}", decrypted_string);
         }
@@ -226,11 +775,11 @@ mod tests {
         let original_plaintext = "This is a secret message used for testing encryption and decryption!";
 
         println!("\n[Test] Starting encryption...");
-        let encrypted_string = encrypt_symmetric(original_plaintext, &public_key_path)?;
+        let encrypted_string = encrypt_symmetric(original_plaintext, &public_key_path, CipherAlgorithm::Aes256Gcm)?;
         println!("[Test] Encryption complete, encrypted string: {}", encrypted_string);
 
         println!("[Test] Starting decryption...");
-        let decrypted_plaintext = decrypt_symmetric(&encrypted_string, &private_key_path)?;
+        let decrypted_plaintext = decrypt_symmetric(&encrypted_string, &private_key_path, None)?;
         println!("[Test] Decryption complete, decrypted plaintext: {}", decrypted_plaintext);
 
         assert_eq!(original_plaintext, decrypted_plaintext, "Decrypted text does not match original text!");
@@ -238,4 +787,140 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_stream_roundtrip_multiple_chunks() -> Result<(), AppError> {
+        use std::io::Cursor;
+
+        let public_key_path = PathBuf::from("public_key.pem");
+        let private_key_path = PathBuf::from("private_key.pem");
+
+        if !public_key_path.exists() || !private_key_path.exists() {
+            eprintln!("\nWarning: Key files not found, skipping stream round-trip test.\n");
+            return Ok(());
+        }
+
+        // A few bytes over two chunk boundaries, so the loop exercises more than one chunk.
+        let original_plaintext: Vec<u8> = (0..(STREAM_CHUNK_SIZE * 2 + 123))
+            .map(|i| (i % 251) as u8)
+            .collect();
+
+        let mut ciphertext_stream = Vec::new();
+        encrypt_stream(Cursor::new(&original_plaintext), &mut ciphertext_stream, &public_key_path)?;
+
+        let mut decrypted = Vec::new();
+        decrypt_stream(Cursor::new(&ciphertext_stream), &mut decrypted, &private_key_path)?;
+
+        assert_eq!(original_plaintext, decrypted);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_detects_truncation() -> Result<(), AppError> {
+        use std::io::Cursor;
+
+        let public_key_path = PathBuf::from("public_key.pem");
+        let private_key_path = PathBuf::from("private_key.pem");
+
+        if !public_key_path.exists() || !private_key_path.exists() {
+            eprintln!("\nWarning: Key files not found, skipping stream truncation test.\n");
+            return Ok(());
+        }
+
+        let original_plaintext: Vec<u8> = (0..(STREAM_CHUNK_SIZE + 10)).map(|i| (i % 251) as u8).collect();
+
+        let mut ciphertext_stream = Vec::new();
+        encrypt_stream(Cursor::new(&original_plaintext), &mut ciphertext_stream, &public_key_path)?;
+
+        // Drop the final byte so decrypt's read_exact on the truncated last chunk fails.
+        ciphertext_stream.pop();
+
+        let mut decrypted = Vec::new();
+        let result = decrypt_stream(Cursor::new(&ciphertext_stream), &mut decrypted, &private_key_path);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_envelope_roundtrip_all_algorithms() -> Result<(), AppError> {
+        let public_key_path = PathBuf::from("public_key.pem");
+        let private_key_path = PathBuf::from("private_key.pem");
+
+        if !public_key_path.exists() || !private_key_path.exists() {
+            eprintln!("\nWarning: Key files not found, skipping envelope round-trip test.\n");
+            return Ok(());
+        }
+
+        let original_plaintext = "The versioned envelope should round-trip under every supported algorithm.";
+
+        for algorithm in [
+            CipherAlgorithm::Aes256Gcm,
+            CipherAlgorithm::Aes256Cbc,
+            CipherAlgorithm::Aes128Ctr,
+            CipherAlgorithm::Aes256Ctr,
+        ] {
+            let encrypted_string = encrypt_symmetric(original_plaintext, &public_key_path, algorithm)?;
+            assert!(encrypted_string.starts_with(&format!("v1:{}:", algorithm.tag())));
+
+            let decrypted_plaintext = decrypt_symmetric(&encrypted_string, &private_key_path, None)?;
+            assert_eq!(original_plaintext, decrypted_plaintext, "mismatch for {:?}", algorithm);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ecies_encryption_decryption_flow() -> Result<(), AppError> {
+        let public_key_path = PathBuf::from("ec_public_key.pem");
+        let private_key_path = PathBuf::from("ec_private_key.pem");
+
+        if !public_key_path.exists() || !private_key_path.exists() {
+            eprintln!("\nWarning: EC key files not found. Please generate them with OpenSSL commands:");
+            eprintln!("  openssl ecparam -genkey -name prime256v1 -noout | openssl pkcs8 -topk8 -nocrypt -out ec_private_key.pem");
+            eprintln!("  openssl pkey -in ec_private_key.pem -pubout -out ec_public_key.pem");
+            eprintln!("Skipping test.\n");
+            return Ok(());
+        }
+
+        let original_plaintext = "This is a secret message used for testing ECIES encryption and decryption!";
+
+        let encrypted_string = encrypt_ecies(original_plaintext, &public_key_path)?;
+        let decrypted_plaintext = decrypt_ecies(&encrypted_string, &private_key_path)?;
+
+        assert_eq!(original_plaintext, decrypted_plaintext, "Decrypted text does not match original text!");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_keystore_scrypt_roundtrip() -> Result<(), AppError> {
+        let original_plaintext = "This is a secret message protected only by a passphrase!";
+        let passphrase = "correct horse battery staple";
+
+        let keystore_json = encrypt_with_passphrase(original_plaintext, passphrase, KdfKind::Scrypt)?;
+        let decrypted_plaintext = decrypt_with_passphrase(&keystore_json, passphrase)?;
+
+        assert_eq!(original_plaintext, decrypted_plaintext);
+        Ok(())
+    }
+
+    #[test]
+    fn test_keystore_pbkdf2_roundtrip() -> Result<(), AppError> {
+        let original_plaintext = "Another secret, this time derived with PBKDF2.";
+        let passphrase = "correct horse battery staple";
+
+        let keystore_json = encrypt_with_passphrase(original_plaintext, passphrase, KdfKind::Pbkdf2)?;
+        let decrypted_plaintext = decrypt_with_passphrase(&keystore_json, passphrase)?;
+
+        assert_eq!(original_plaintext, decrypted_plaintext);
+        Ok(())
+    }
+
+    #[test]
+    fn test_keystore_wrong_passphrase_fails_mac() -> Result<(), AppError> {
+        let keystore_json = encrypt_with_passphrase("top secret", "correct passphrase", KdfKind::Scrypt)?;
+        let result = decrypt_with_passphrase(&keystore_json, "wrong passphrase");
+        assert!(result.is_err());
+        Ok(())
+    }
 }
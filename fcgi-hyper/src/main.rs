@@ -1,19 +1,29 @@
+use base64::Engine;
+use bytes::Buf;
 use clap::Parser;
+use futures_util::{SinkExt, StreamExt};
 use http::request::Parts;
 use http_body_util::{BodyExt, Full};
 use hyper::body::{Bytes, Incoming};
-use hyper::header::{HeaderName, HeaderValue};
+use hyper::header::{HeaderName, HeaderValue, CONNECTION, UPGRADE};
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use hyper::{Request, Response, StatusCode};
 use hyper_util::rt::TokioIo;
-use log::{error, info};
+use log::{error, info, warn};
+use sha1::{Digest, Sha1};
 use std::collections::{BTreeMap, HashMap};
 use std::convert::Infallible;
-use std::net::SocketAddr;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::path::PathBuf;
-use tokio::net::{TcpListener, UnixListener};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream, UnixListener};
 use tokio_fastcgi::Requests;
+use tokio_rustls::TlsAcceptor;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
 
 // Re-implement the header capitalization logic from fcgi-app
 fn capitalize_header_name(name: &str) -> String {
@@ -30,18 +40,169 @@ fn capitalize_header_name(name: &str) -> String {
         .join("-")
 }
 
+/// Negotiated TLS parameters for a connection, surfaced in the response so
+/// the tool can confirm what a TLS-terminating proxy/CDN actually negotiated.
+#[derive(Debug, Clone)]
+struct TlsInfo {
+    version: Option<String>,
+    alpn: Option<String>,
+}
+
+/// Marks a request's transport as one that can carry a hyper connection
+/// upgrade (plain/TLS HTTP). Absent (or false) for FastCGI, which has no
+/// such mechanism.
+#[derive(Debug, Clone, Copy)]
+struct SupportsUpgrade(bool);
+
+/// Cap on how many request body bytes `unified_service` reads and echoes
+/// back, set from `Args::max_body_bytes` so large uploads can't exhaust
+/// memory.
+#[derive(Debug, Clone, Copy)]
+struct MaxBodyBytes(usize);
+
+/// Reads up to `max_bytes` of `body`'s data frames, silently dropping
+/// trailers and anything past the cap rather than erroring out a debug tool
+/// over an oversized upload.
+async fn read_body_capped<B>(mut body: B, max_bytes: usize) -> Bytes
+where
+    B: hyper::body::Body + Unpin,
+{
+    let mut buf = Vec::new();
+    while let Some(Ok(frame)) = body.frame().await {
+        if buf.len() >= max_bytes {
+            continue;
+        }
+        if let Ok(mut data) = frame.into_data() {
+            let remaining = max_bytes - buf.len();
+            let take = remaining.min(data.remaining());
+            buf.extend_from_slice(&data.copy_to_bytes(take));
+        }
+    }
+    Bytes::from(buf)
+}
+
+/// GUID appended to the client's `Sec-WebSocket-Key` before hashing, fixed by
+/// RFC 6455.
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Default value of `Args::max_body_bytes` when the flag isn't given.
+const DEFAULT_MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// True if `headers` carries a `Connection: Upgrade` / `Upgrade: websocket`
+/// pair, i.e. a WebSocket handshake request.
+fn is_websocket_upgrade(headers: &hyper::HeaderMap) -> bool {
+    let connection_has_upgrade = headers
+        .get(CONNECTION)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_lowercase().contains("upgrade"))
+        .unwrap_or(false);
+    let upgrade_is_websocket = headers
+        .get(UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.eq_ignore_ascii_case("websocket"))
+        .unwrap_or(false);
+    connection_has_upgrade && upgrade_is_websocket
+}
+
+/// Computes the `Sec-WebSocket-Accept` value for `client_key` per RFC 6455:
+/// `base64(SHA1(key + GUID))`.
+fn websocket_accept_key(client_key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(client_key.as_bytes());
+    hasher.update(WEBSOCKET_GUID.as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(hasher.finalize())
+}
+
+/// Echoes text/binary frames and replies to pings with pongs until the peer
+/// closes the connection or a frame can't be read.
+async fn run_websocket_echo<S>(ws_stream: WebSocketStream<S>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut write, mut read) = ws_stream.split();
+    while let Some(msg) = read.next().await {
+        let outgoing = match msg {
+            Ok(Message::Text(text)) => Message::Text(text),
+            Ok(Message::Binary(data)) => Message::Binary(data),
+            Ok(Message::Ping(payload)) => Message::Pong(payload),
+            Ok(Message::Close(_)) | Err(_) => break,
+            Ok(_) => continue,
+        };
+        if write.send(outgoing).await.is_err() {
+            break;
+        }
+    }
+}
+
 // Unified service function to handle requests from different sources.
-async fn unified_service<B>(req: Request<B>) -> Result<Response<Full<Bytes>>, Infallible>
+async fn unified_service<B>(mut req: Request<B>) -> Result<Response<Full<Bytes>>, Infallible>
 where
-    B: hyper::body::Body,
+    B: hyper::body::Body + Send + Unpin + 'static,
 {
     let remote_addr = req
         .extensions()
         .get::<String>()
         .cloned()
         .unwrap_or_else(|| "Unknown".to_string());
+    let tls_info = req.extensions().get::<TlsInfo>().cloned();
+    let supports_upgrade = req.extensions().get::<SupportsUpgrade>().copied().unwrap_or(SupportsUpgrade(false)).0;
+
+    if is_websocket_upgrade(req.headers()) {
+        if !supports_upgrade {
+            let response = Response::builder()
+                .status(StatusCode::NOT_IMPLEMENTED)
+                .header("Content-Type", "text/plain")
+                .body(Full::new(Bytes::from("WebSocket upgrades are not supported over FastCGI.\r\n")))
+                .unwrap();
+            return Ok(response);
+        }
+
+        if let Some(accept_key) = req
+            .headers()
+            .get("sec-websocket-key")
+            .and_then(|v| v.to_str().ok())
+            .map(websocket_accept_key)
+        {
+            tokio::spawn(async move {
+                match hyper::upgrade::on(&mut req).await {
+                    Ok(upgraded) => {
+                        let ws_stream = WebSocketStream::from_raw_socket(TokioIo::new(upgraded), Role::Server, None).await;
+                        run_websocket_echo(ws_stream).await;
+                    }
+                    Err(e) => error!("WebSocket upgrade failed: {}", e),
+                }
+            });
+
+            let response = Response::builder()
+                .status(StatusCode::SWITCHING_PROTOCOLS)
+                .header("Upgrade", "websocket")
+                .header("Connection", "Upgrade")
+                .header("Sec-WebSocket-Accept", accept_key)
+                .body(Full::new(Bytes::new()))
+                .unwrap();
+            return Ok(response);
+        }
+    }
+
+    let max_body_bytes = req.extensions().get::<MaxBodyBytes>().copied().unwrap_or(MaxBodyBytes(DEFAULT_MAX_BODY_BYTES)).0;
+    let (parts, body) = req.into_parts();
+    let body_bytes = read_body_capped(body, max_body_bytes).await;
+    let body_str = build_request_dump(&parts, &body_bytes, &remote_addr, tls_info);
 
-    let (parts, _body) = req.into_parts();
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/plain")
+        .body(Full::new(Bytes::from(body_str)))
+        .unwrap();
+
+    Ok(response)
+}
+
+/// Formats the request-detail dump shared by the HTTP/1.1, FastCGI, and
+/// HTTP/3 listeners: method/URI/version, remote address, TLS/QUIC
+/// parameters (if any), sorted headers, the decoded body, and the process's
+/// environment variables.
+fn build_request_dump(parts: &Parts, body_bytes: &Bytes, remote_addr: &str, tls_info: Option<TlsInfo>) -> String {
     let mut body_str = String::new();
 
     body_str.push_str("--- Request Details ---\r\n");
@@ -49,6 +210,10 @@ where
     body_str.push_str(&format!("URI: {}\r\n", parts.uri));
     body_str.push_str(&format!("Version: {:?}\r\n", parts.version));
     body_str.push_str(&format!("Remote Address: {}\r\n", remote_addr));
+    if let Some(tls_info) = tls_info {
+        body_str.push_str(&format!("TLS Version: {}\r\n", tls_info.version.unwrap_or_else(|| "Unknown".to_string())));
+        body_str.push_str(&format!("ALPN Protocol: {}\r\n", tls_info.alpn.unwrap_or_else(|| "None".to_string())));
+    }
 
     body_str.push_str("\r\n--- HTTP Headers ---\r\n");
     let mut sorted_headers = BTreeMap::new();
@@ -66,6 +231,19 @@ where
         }
     }
 
+    body_str.push_str("\r\n--- Request Body ---\r\n");
+    body_str.push_str(&format!("Length: {} bytes\r\n", body_bytes.len()));
+    let content_type = parts.headers.get(hyper::header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or("");
+    if content_type.starts_with("application/x-www-form-urlencoded") {
+        body_str.push_str("Decoded Form Fields:\r\n");
+        for (key, value) in form_urlencoded::parse(body_bytes) {
+            body_str.push_str(&format!("  {} = {}\r\n", key, value));
+        }
+    }
+    body_str.push_str("Raw Body:\r\n");
+    body_str.push_str(&String::from_utf8_lossy(body_bytes));
+    body_str.push_str("\r\n");
+
     body_str.push_str("\r\n--- Process Environment Variables ---\r\n");
     let mut env_vars: Vec<String> = std::env::vars()
         .map(|(key, value)| format!("{}={}", key, value))
@@ -75,13 +253,7 @@ where
         body_str.push_str(&format!("{}\r\n", env_var));
     }
 
-    let response = Response::builder()
-        .status(StatusCode::OK)
-        .header("Content-Type", "text/plain")
-        .body(Full::new(Bytes::from(body_str)))
-        .unwrap();
-
-    Ok(response)
+    body_str
 }
 
 #[derive(Parser, Debug)]
@@ -91,9 +263,182 @@ struct Args {
     http: Option<String>,
     #[arg(value_name = "SOCKET_PATH")]
     socket: Option<PathBuf>,
+
+    /// Parse a PROXY protocol v1/v2 header at the start of each TCP
+    /// connection and report the client address it carries instead of the
+    /// TCP peer, for deployments behind a TLS-terminating load balancer or
+    /// reverse proxy that speaks PROXY protocol.
+    #[arg(long)]
+    proxy_protocol: bool,
+
+    /// Address for an HTTPS listener; requires --tls-cert and --tls-key.
+    #[arg(long)]
+    https: Option<String>,
+
+    /// PEM-encoded TLS certificate chain, used together with --tls-key.
+    #[arg(long)]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM-encoded TLS private key, used together with --tls-cert.
+    #[arg(long)]
+    tls_key: Option<PathBuf>,
+
+    /// Address for a FastCGI responder listening over TCP instead of a Unix
+    /// socket or stdin, e.g. for a sidecar container. Ignored if `socket` is
+    /// also given.
+    #[arg(long)]
+    fcgi_tcp: Option<String>,
+
+    /// Largest request body, in bytes, that will be read and echoed back in
+    /// the "--- Request Body ---" section.
+    #[arg(long, default_value_t = DEFAULT_MAX_BODY_BYTES)]
+    max_body_bytes: usize,
+
+    /// Address for an HTTP/3 (QUIC) listener; requires --tls-cert and
+    /// --tls-key, same as --https.
+    #[arg(long)]
+    http3: Option<String>,
+}
+
+/// Loads a PEM cert chain and private key into a `rustls::ServerConfig`,
+/// shared by the `--https` and `--http3` listeners (which differ only in
+/// the ALPN token they advertise).
+fn load_rustls_server_config(cert_path: &PathBuf, key_path: &PathBuf) -> Result<rustls::ServerConfig, Box<dyn std::error::Error>> {
+    let cert_file = std::fs::File::open(cert_path)?;
+    let certs = rustls_pemfile::certs(&mut std::io::BufReader::new(cert_file)).collect::<Result<Vec<_>, _>>()?;
+
+    let key_file = std::fs::File::open(key_path)?;
+    let key = rustls_pemfile::private_key(&mut std::io::BufReader::new(key_file))?
+        .ok_or("no private key found in --tls-key file")?;
+
+    Ok(rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?)
+}
+
+/// Builds a `tokio_rustls::TlsAcceptor` for the `--https` listener.
+fn build_tls_acceptor(cert_path: &PathBuf, key_path: &PathBuf) -> Result<TlsAcceptor, Box<dyn std::error::Error>> {
+    let tls_config = load_rustls_server_config(cert_path, key_path)?;
+    Ok(TlsAcceptor::from(Arc::new(tls_config)))
+}
+
+/// ALPN token HTTP/3 clients negotiate over QUIC.
+const H3_ALPN: &[u8] = b"h3";
+
+/// Builds a `quinn::ServerConfig` for the `--http3` listener, reusing the
+/// same cert/key files as `--https` but advertising the `h3` ALPN token.
+fn build_h3_server_config(cert_path: &PathBuf, key_path: &PathBuf) -> Result<quinn::ServerConfig, Box<dyn std::error::Error>> {
+    let mut tls_config = load_rustls_server_config(cert_path, key_path)?;
+    tls_config.alpn_protocols = vec![H3_ALPN.to_vec()];
+
+    let quic_tls_config = quinn::crypto::rustls::QuicServerConfig::try_from(tls_config)?;
+    Ok(quinn::ServerConfig::with_crypto(Arc::new(quic_tls_config)))
+}
+
+/// 12-byte signature that opens every PROXY protocol v2 header.
+const PROXY_V2_SIGNATURE: [u8; 12] = [0x0D, 0x0A, 0x0D, 0x0A, 0x00, 0x0D, 0x0A, 0x51, 0x55, 0x49, 0x54, 0x0A];
+
+/// Longest a PROXY protocol v1 header line is allowed to be, per spec.
+const PROXY_V1_MAX_LEN: usize = 107;
+
+/// Peeks at the start of `stream` for a PROXY protocol v1 or v2 header and,
+/// if one is present, consumes exactly its bytes and returns the client
+/// address it carries. Falls back to `peer_addr` for plain connections, for
+/// `UNKNOWN`/`LOCAL` headers, and for anything malformed.
+async fn read_proxy_header(stream: &mut TcpStream, peer_addr: SocketAddr) -> SocketAddr {
+    let mut peek_buf = [0u8; 16];
+    let peeked = match stream.peek(&mut peek_buf).await {
+        Ok(n) => n,
+        Err(_) => return peer_addr,
+    };
+
+    if peeked >= PROXY_V2_SIGNATURE.len() && peek_buf[..PROXY_V2_SIGNATURE.len()] == PROXY_V2_SIGNATURE {
+        read_proxy_v2(stream, peer_addr).await
+    } else if peeked >= 6 && &peek_buf[..6] == b"PROXY " {
+        read_proxy_v1(stream, peer_addr).await
+    } else {
+        peer_addr
+    }
 }
 
-async fn run_http(addr_str: String) {
+/// Reads a `PROXY TCP4|TCP6|UNKNOWN <src-ip> <dst-ip> <src-port> <dst-port>\r\n`
+/// line and returns `<src-ip>:<src-port>` as the client address.
+async fn read_proxy_v1(stream: &mut TcpStream, peer_addr: SocketAddr) -> SocketAddr {
+    let mut peek_buf = [0u8; PROXY_V1_MAX_LEN];
+    let peeked = match stream.peek(&mut peek_buf).await {
+        Ok(n) => n,
+        Err(_) => return peer_addr,
+    };
+
+    let header_len = match peek_buf[..peeked].windows(2).position(|w| w == b"\r\n") {
+        Some(pos) => pos + 2,
+        None => {
+            warn!("PROXY v1 header from {} missing a terminating CRLF within {} bytes", peer_addr, PROXY_V1_MAX_LEN);
+            return peer_addr;
+        }
+    };
+
+    let mut header_buf = vec![0u8; header_len];
+    if stream.read_exact(&mut header_buf).await.is_err() {
+        return peer_addr;
+    }
+
+    let line = String::from_utf8_lossy(&header_buf[..header_len - 2]);
+    match line.split(' ').collect::<Vec<&str>>().as_slice() {
+        ["PROXY", "TCP4" | "TCP6", src_ip, _dst_ip, src_port, _dst_port] => {
+            match (src_ip.parse::<std::net::IpAddr>(), src_port.parse::<u16>()) {
+                (Ok(ip), Ok(port)) => SocketAddr::new(ip, port),
+                _ => peer_addr,
+            }
+        }
+        _ => peer_addr,
+    }
+}
+
+/// Reads a binary PROXY protocol v2 header (12-byte signature, then
+/// ver/cmd, family/transport, and a big-endian address block length) and
+/// returns the embedded source address for `PROXY` TCP4/TCP6 headers.
+async fn read_proxy_v2(stream: &mut TcpStream, peer_addr: SocketAddr) -> SocketAddr {
+    let mut header = [0u8; 16];
+    if stream.read_exact(&mut header).await.is_err() {
+        return peer_addr;
+    }
+
+    let ver_cmd = header[12];
+    let version = ver_cmd >> 4;
+    let command = ver_cmd & 0x0F;
+    let fam_proto = header[13];
+    let addr_len = u16::from_be_bytes([header[14], header[15]]) as usize;
+
+    let mut addr_buf = vec![0u8; addr_len];
+    if stream.read_exact(&mut addr_buf).await.is_err() {
+        return peer_addr;
+    }
+
+    // LOCAL (health check) connections and anything but version 2 keep the
+    // OS-reported peer; only a PROXY command carries a client address.
+    if version != 2 || command != 0x01 {
+        return peer_addr;
+    }
+
+    match fam_proto {
+        0x11 if addr_buf.len() >= 12 => {
+            let src_ip = Ipv4Addr::new(addr_buf[0], addr_buf[1], addr_buf[2], addr_buf[3]);
+            let src_port = u16::from_be_bytes([addr_buf[8], addr_buf[9]]);
+            SocketAddr::new(src_ip.into(), src_port)
+        }
+        0x21 if addr_buf.len() >= 36 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&addr_buf[0..16]);
+            let src_ip = Ipv6Addr::from(octets);
+            let src_port = u16::from_be_bytes([addr_buf[32], addr_buf[33]]);
+            SocketAddr::new(src_ip.into(), src_port)
+        }
+        _ => peer_addr, // UNKNOWN family/transport: keep the OS-reported peer.
+    }
+}
+
+async fn run_http(addr_str: String, proxy_protocol: bool, max_body_bytes: usize) {
     info!("Starting HTTP server on address: {}", addr_str);
     let addr: SocketAddr = match addr_str.parse() {
         Ok(a) => a,
@@ -112,7 +457,7 @@ async fn run_http(addr_str: String) {
     };
 
     loop {
-        let (stream, remote_addr) = match listener.accept().await {
+        let (mut stream, peer_addr) = match listener.accept().await {
             Ok(s) => s,
             Err(e) => {
                 error!("Failed to accept connection: {}", e);
@@ -120,22 +465,209 @@ async fn run_http(addr_str: String) {
             }
         };
 
-        let io = TokioIo::new(stream);
+        tokio::task::spawn(async move {
+            let remote_addr = if proxy_protocol {
+                read_proxy_header(&mut stream, peer_addr).await
+            } else {
+                peer_addr
+            };
+
+            let io = TokioIo::new(stream);
+            let service = service_fn(move |mut req: Request<Incoming>| {
+                req.extensions_mut().insert(remote_addr.to_string());
+                req.extensions_mut().insert(SupportsUpgrade(true));
+                req.extensions_mut().insert(MaxBodyBytes(max_body_bytes));
+                unified_service(req)
+            });
+
+            if let Err(err) = http1::Builder::new().serve_connection(io, service).with_upgrades().await {
+                error!("Error serving connection: {:?}", err);
+            }
+        });
+    }
+}
+
+async fn run_https(addr_str: String, acceptor: TlsAcceptor, proxy_protocol: bool, max_body_bytes: usize) {
+    info!("Starting HTTPS server on address: {}", addr_str);
+    let addr: SocketAddr = match addr_str.parse() {
+        Ok(a) => a,
+        Err(e) => {
+            error!("Failed to parse HTTPS address {}: {}", addr_str, e);
+            return;
+        }
+    };
+
+    let listener = match TcpListener::bind(addr).await {
+        Ok(l) => l,
+        Err(e) => {
+            error!("Failed to bind to TCP socket {}: {}", addr, e);
+            return;
+        }
+    };
+
+    loop {
+        let (mut stream, peer_addr) = match listener.accept().await {
+            Ok(s) => s,
+            Err(e) => {
+                error!("Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
 
         tokio::task::spawn(async move {
+            // PROXY protocol, if present, is plaintext and precedes the TLS
+            // handshake, so it must be parsed off the raw TCP stream first.
+            let remote_addr = if proxy_protocol {
+                read_proxy_header(&mut stream, peer_addr).await
+            } else {
+                peer_addr
+            };
+
+            let tls_stream = match acceptor.accept(stream).await {
+                Ok(s) => s,
+                Err(e) => {
+                    error!("TLS handshake failed: {}, from: {}", e, peer_addr);
+                    return;
+                }
+            };
+
+            let (_, server_conn) = tls_stream.get_ref();
+            let tls_info = TlsInfo {
+                version: server_conn.protocol_version().map(|v| format!("{:?}", v)),
+                alpn: server_conn.alpn_protocol().map(|p| String::from_utf8_lossy(p).to_string()),
+            };
+
+            let io = TokioIo::new(tls_stream);
             let service = service_fn(move |mut req: Request<Incoming>| {
                 req.extensions_mut().insert(remote_addr.to_string());
+                req.extensions_mut().insert(tls_info.clone());
+                req.extensions_mut().insert(SupportsUpgrade(true));
+                req.extensions_mut().insert(MaxBodyBytes(max_body_bytes));
                 unified_service(req)
             });
 
-            if let Err(err) = http1::Builder::new().serve_connection(io, service).await {
+            if let Err(err) = http1::Builder::new().serve_connection(io, service).with_upgrades().await {
                 error!("Error serving connection: {:?}", err);
             }
         });
     }
 }
 
-async fn handle_fcgi_request<W>(request: tokio_fastcgi::Request<W>) -> Result<(), std::io::Error>
+/// Runs the `--http3` listener: a QUIC endpoint speaking HTTP/3 via `h3`,
+/// sharing the same `--tls-cert`/`--tls-key` files as `--https`. Each
+/// bidirectional request stream is handed to `handle_h3_request` so the
+/// reported output matches the HTTP/1.1 and FastCGI listeners.
+async fn run_http3(addr_str: String, server_config: quinn::ServerConfig, max_body_bytes: usize) {
+    let addr: SocketAddr = match addr_str.parse() {
+        Ok(a) => a,
+        Err(e) => {
+            error!("Failed to parse HTTP/3 address {}: {}", addr_str, e);
+            return;
+        }
+    };
+
+    let endpoint = match quinn::Endpoint::server(server_config, addr) {
+        Ok(e) => e,
+        Err(e) => {
+            error!("Failed to bind QUIC endpoint on {}: {}", addr, e);
+            return;
+        }
+    };
+    info!("Starting HTTP/3 server on address: {}", addr);
+
+    while let Some(incoming) = endpoint.accept().await {
+        tokio::spawn(async move {
+            let connection = match incoming.await {
+                Ok(c) => c,
+                Err(e) => {
+                    error!("QUIC handshake failed: {}", e);
+                    return;
+                }
+            };
+            let remote_addr = connection.remote_address().to_string();
+
+            let handshake_data = connection
+                .handshake_data()
+                .and_then(|data| data.downcast::<quinn::crypto::rustls::HandshakeData>().ok());
+            let tls_info = TlsInfo {
+                version: Some("QUIC/h3".to_string()),
+                alpn: handshake_data
+                    .and_then(|data| data.protocol)
+                    .map(|p| String::from_utf8_lossy(&p).to_string()),
+            };
+
+            let mut h3_conn = match h3::server::Connection::new(h3_quinn::Connection::new(connection)).await {
+                Ok(c) => c,
+                Err(e) => {
+                    error!("HTTP/3 connection setup failed, from: {}: {}", remote_addr, e);
+                    return;
+                }
+            };
+
+            loop {
+                match h3_conn.accept().await {
+                    Ok(Some((req, stream))) => {
+                        let remote_addr = remote_addr.clone();
+                        let tls_info = tls_info.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = handle_h3_request(req, stream, remote_addr, tls_info, max_body_bytes).await {
+                                error!("Error handling HTTP/3 request: {}", e);
+                            }
+                        });
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        error!("HTTP/3 accept error, from: {}: {}", remote_addr, e);
+                        break;
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Drains an `h3` request stream's body (capped the same way the hyper and
+/// FastCGI paths cap theirs), builds the shared request dump, and sends it
+/// back as the response.
+async fn handle_h3_request<S>(
+    req: Request<()>,
+    mut stream: h3::server::RequestStream<S, Bytes>,
+    remote_addr: String,
+    tls_info: TlsInfo,
+    max_body_bytes: usize,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    S: h3::quic::BidiStream<Bytes>,
+{
+    let (parts, _) = req.into_parts();
+
+    let mut body_bytes = Vec::new();
+    while let Some(mut chunk) = stream.recv_data().await? {
+        if body_bytes.len() >= max_body_bytes {
+            continue;
+        }
+        let remaining = max_body_bytes - body_bytes.len();
+        let take = remaining.min(chunk.remaining());
+        body_bytes.extend_from_slice(&chunk.copy_to_bytes(take));
+    }
+
+    let body_str = build_request_dump(&parts, &Bytes::from(body_bytes), &remote_addr, Some(tls_info));
+
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/plain")
+        .body(())
+        .unwrap();
+
+    stream.send_response(response).await?;
+    stream.send_data(Bytes::from(body_str)).await?;
+    stream.finish().await?;
+
+    Ok(())
+}
+
+async fn handle_fcgi_request<W>(mut request: tokio_fastcgi::Request<W>, max_body_bytes: usize) -> Result<(), std::io::Error>
 where
     W: tokio::io::AsyncWrite + Unpin,
 {
@@ -146,10 +678,23 @@ where
         }
     }
 
+    // Drain the FCGI_STDIN stream (the POST/PUT body) before building the
+    // HTTP request, capped the same way the hyper side caps its body.
+    let mut stdin_body = Vec::new();
+    while let Some(Ok(chunk)) = request.next().await {
+        if stdin_body.len() >= max_body_bytes {
+            continue;
+        }
+        let remaining = max_body_bytes - stdin_body.len();
+        let take = remaining.min(chunk.len());
+        stdin_body.extend_from_slice(&chunk[..take]);
+    }
+
     let (http_parts, remote_addr) = fcgi_params_to_http_parts(&params);
 
-    let mut req = Request::from_parts(http_parts, Full::new(Bytes::new()));
+    let mut req = Request::from_parts(http_parts, Full::new(Bytes::from(stdin_body)));
     req.extensions_mut().insert(remote_addr);
+    req.extensions_mut().insert(MaxBodyBytes(max_body_bytes));
 
     let http_res = unified_service(req).await.unwrap();
     let (parts, body) = http_res.into_parts();
@@ -171,46 +716,83 @@ where
     Ok(())
 }
 
-async fn run_fcgi(socket_path: Option<PathBuf>) {
-    if let Some(path) = socket_path {
-        info!("Starting FastCGI server on socket: {:?}", path);
-        if path.exists() {
-            if let Err(e) = tokio::fs::remove_file(&path).await {
-                error!("Failed to remove existing socket file {:?}: {}", path, e);
-                return;
-            }
+/// Drains every FastCGI request on one connection's already-split
+/// reader/writer, shared by the Unix socket, TCP, and stdin transports so
+/// they differ only in how the connection was obtained.
+async fn serve_fcgi_connection<R, W>(reader: R, writer: W, max_body_bytes: usize)
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    let mut requests = Requests::from_split_socket((reader, writer), 10, 10);
+    while let Ok(Some(request)) = requests.next().await {
+        if let Err(err) = handle_fcgi_request(request, max_body_bytes).await {
+            error!("Error processing FCGI request: {}", err);
         }
-        let listener = match UnixListener::bind(&path) {
-            Ok(l) => l,
-            Err(e) => {
-                error!("Failed to bind to Unix socket {:?}: {}", path, e);
-                return;
+    }
+}
+
+async fn run_fcgi(socket_path: Option<PathBuf>, fcgi_tcp: Option<String>, max_body_bytes: usize) {
+    match (socket_path, fcgi_tcp) {
+        (Some(path), _) => {
+            info!("Starting FastCGI server on socket: {:?}", path);
+            if path.exists() {
+                if let Err(e) = tokio::fs::remove_file(&path).await {
+                    error!("Failed to remove existing socket file {:?}: {}", path, e);
+                    return;
+                }
             }
-        };
+            let listener = match UnixListener::bind(&path) {
+                Ok(l) => l,
+                Err(e) => {
+                    error!("Failed to bind to Unix socket {:?}: {}", path, e);
+                    return;
+                }
+            };
 
-        loop {
-            if let Ok((socket, _)) = listener.accept().await {
-                tokio::spawn(async move {
-                    let (reader, writer) = socket.into_split();
-                    let mut requests = Requests::from_split_socket((reader, writer), 10, 10);
-                    while let Ok(Some(request)) = requests.next().await {
-                        if let Err(err) = handle_fcgi_request(request).await {
-                            error!("Error processing FCGI request: {}", err);
-                        }
-                    }
-                });
+            loop {
+                if let Ok((socket, _)) = listener.accept().await {
+                    tokio::spawn(async move {
+                        let (reader, writer) = socket.into_split();
+                        serve_fcgi_connection(reader, writer, max_body_bytes).await;
+                    });
+                }
             }
         }
-    } else {
-        info!("Starting FastCGI server on stdin");
-        let stdin = tokio::io::stdin();
-        let stdout = tokio::io::stdout();
-        let mut requests = Requests::from_split_socket((stdin, stdout), 10, 10);
-        while let Ok(Some(request)) = requests.next().await {
-            if let Err(err) = handle_fcgi_request(request).await {
-                error!("Error processing FCGI request: {}", err);
+        (None, Some(addr_str)) => {
+            info!("Starting FastCGI server on TCP address: {}", addr_str);
+            let addr: SocketAddr = match addr_str.parse() {
+                Ok(a) => a,
+                Err(e) => {
+                    error!("Failed to parse FastCGI TCP address {}: {}", addr_str, e);
+                    return;
+                }
+            };
+            let listener = match TcpListener::bind(addr).await {
+                Ok(l) => l,
+                Err(e) => {
+                    error!("Failed to bind to TCP socket {}: {}", addr, e);
+                    return;
+                }
+            };
+
+            loop {
+                match listener.accept().await {
+                    Ok((socket, peer_addr)) => {
+                        info!("Accepted FastCGI connection from: {}", peer_addr);
+                        tokio::spawn(async move {
+                            let (reader, writer) = socket.into_split();
+                            serve_fcgi_connection(reader, writer, max_body_bytes).await;
+                        });
+                    }
+                    Err(e) => error!("Failed to accept FastCGI TCP connection: {}", e),
+                }
             }
         }
+        (None, None) => {
+            info!("Starting FastCGI server on stdin");
+            serve_fcgi_connection(tokio::io::stdin(), tokio::io::stdout(), max_body_bytes).await;
+        }
     }
 }
 
@@ -267,9 +849,25 @@ async fn main() {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     let args = Args::parse();
 
-    if let Some(addr_str) = args.http {
-        run_http(addr_str).await;
+    if let Some(addr_str) = args.https {
+        match (&args.tls_cert, &args.tls_key) {
+            (Some(cert_path), Some(key_path)) => match build_tls_acceptor(cert_path, key_path) {
+                Ok(acceptor) => run_https(addr_str, acceptor, args.proxy_protocol, args.max_body_bytes).await,
+                Err(e) => error!("Failed to build TLS acceptor: {}", e),
+            },
+            _ => error!("--https requires both --tls-cert and --tls-key"),
+        }
+    } else if let Some(addr_str) = args.http3 {
+        match (&args.tls_cert, &args.tls_key) {
+            (Some(cert_path), Some(key_path)) => match build_h3_server_config(cert_path, key_path) {
+                Ok(server_config) => run_http3(addr_str, server_config, args.max_body_bytes).await,
+                Err(e) => error!("Failed to build HTTP/3 server config: {}", e),
+            },
+            _ => error!("--http3 requires both --tls-cert and --tls-key"),
+        }
+    } else if let Some(addr_str) = args.http {
+        run_http(addr_str, args.proxy_protocol, args.max_body_bytes).await;
     } else {
-        run_fcgi(args.socket).await;
+        run_fcgi(args.socket, args.fcgi_tcp, args.max_body_bytes).await;
     }
 }
@@ -1,4 +1,4 @@
-use unix_socket::{run_client, DEFAULT_SOCKET_PATH};
+use unix_socket::{run_client, session_key_from_env, DEFAULT_SOCKET_PATH};
 use std::env;
 
 fn main() {
@@ -9,8 +9,10 @@ fn main() {
         "Hello from client!".to_string()
     };
 
+    let session_key = session_key_from_env();
+
     println!("Sending: {}", message);
-    match run_client(DEFAULT_SOCKET_PATH, &message) {
+    match run_client(DEFAULT_SOCKET_PATH, &message, session_key.as_ref()) {
         Ok(response) => println!("Received: {}", response),
         Err(e) => eprintln!("Client error: {}", e),
     }
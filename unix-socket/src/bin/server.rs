@@ -1,8 +1,13 @@
-use unix_socket::{run_server, DEFAULT_SOCKET_PATH};
+use unix_socket::{run_server, session_key_from_env, DEFAULT_SOCKET_PATH};
 
 fn main() {
-    println!("Starting server on {}", DEFAULT_SOCKET_PATH);
-    if let Err(e) = run_server(DEFAULT_SOCKET_PATH, false) {
+    let session_key = session_key_from_env();
+    println!(
+        "Starting server on {} ({})",
+        DEFAULT_SOCKET_PATH,
+        if session_key.is_some() { "encrypted" } else { "plaintext" }
+    );
+    if let Err(e) = run_server(DEFAULT_SOCKET_PATH, false, session_key.as_ref()) {
         eprintln!("Server error: {}", e);
     }
 }
\ No newline at end of file
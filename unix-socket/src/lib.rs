@@ -1,17 +1,44 @@
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose, Engine as _};
+use rand_core::{OsRng, RngCore};
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::io::{Read, Write};
 use std::fs;
 
 pub const DEFAULT_SOCKET_PATH: &str = "/tmp/my_unix_socket.sock";
 
+/// Size of the pre-shared AES-256-GCM session key used by the encrypted transport.
+pub const SESSION_KEY_LEN: usize = 32;
+
+/// Each encrypted frame is `base64(nonce || ciphertext+tag)` followed by this
+/// delimiter byte, so a message never collides with the framing.
+const MESSAGE_DELIMITER: u8 = b'\n';
+
+/// Reads the pre-shared session key from `UNIX_SOCKET_SESSION_KEY` (hex-encoded),
+/// if set, opting in to the AES-256-GCM encrypted transport.
+pub fn session_key_from_env() -> Option<[u8; SESSION_KEY_LEN]> {
+    let hex_key = std::env::var("UNIX_SOCKET_SESSION_KEY").ok()?;
+    let bytes = hex::decode(hex_key).expect("UNIX_SOCKET_SESSION_KEY must be valid hex");
+    bytes.try_into().ok().or_else(|| {
+        panic!("UNIX_SOCKET_SESSION_KEY must decode to {} bytes", SESSION_KEY_LEN);
+    })
+}
+
 /// Runs the Unix socket server.
 /// If `single_shot` is true, the server will exit after handling one client.
-pub fn run_server(socket_path: &str, single_shot: bool) -> std::io::Result<()> {
+/// If `session_key` is `Some`, messages are expected to be AES-256-GCM
+/// encrypted frames under that pre-shared key; otherwise the plaintext
+/// protocol is used, unchanged, for debugging.
+pub fn run_server(socket_path: &str, single_shot: bool, session_key: Option<&[u8; SESSION_KEY_LEN]>) -> std::io::Result<()> {
     if fs::metadata(socket_path).is_ok() {
         fs::remove_file(socket_path)?;
     }
 
     let listener = UnixListener::bind(socket_path)?;
+    let cipher = session_key.map(make_cipher);
 
     for stream in listener.incoming() {
         match stream {
@@ -19,11 +46,12 @@ pub fn run_server(socket_path: &str, single_shot: bool) -> std::io::Result<()> {
                 // Handle client in a new thread for the main server,
                 // but directly for the single-shot test to ensure completion.
                 if single_shot {
-                    handle_client(stream);
+                    handle_client(stream, cipher.clone());
                     break; // Exit after one client
                 } else {
+                    let cipher = cipher.clone();
                     std::thread::spawn(move || {
-                        handle_client(stream);
+                        handle_client(stream, cipher);
                     });
                 }
             }
@@ -36,23 +64,93 @@ pub fn run_server(socket_path: &str, single_shot: bool) -> std::io::Result<()> {
     Ok(())
 }
 
-fn handle_client(mut stream: UnixStream) {
-    let mut buffer = [0; 1024];
+fn handle_client(mut stream: UnixStream, cipher: Option<Aes256Gcm>) {
+    let mut buffer = [0; 4096];
     let bytes_read = stream.read(&mut buffer).unwrap();
-    let received_message = String::from_utf8_lossy(&buffer[..bytes_read]).to_string();
+
+    let received_message = match &cipher {
+        Some(cipher) => decrypt_frame(cipher, trim_delimiter(&buffer[..bytes_read])).unwrap(),
+        None => buffer[..bytes_read].to_vec(),
+    };
+    let received_message = String::from_utf8_lossy(&received_message).to_string();
     println!("Received: {}", received_message);
 
     let response = received_message.chars().rev().collect::<String>();
-    stream.write_all(response.as_bytes()).unwrap();}
 
-pub fn run_client(socket_path: &str, message: &str) -> std::io::Result<String> {
+    match &cipher {
+        Some(cipher) => {
+            let framed = encrypt_frame(cipher, response.as_bytes());
+            stream.write_all(&framed).unwrap();
+        }
+        None => stream.write_all(response.as_bytes()).unwrap(),
+    }
+}
+
+pub fn run_client(socket_path: &str, message: &str, session_key: Option<&[u8; SESSION_KEY_LEN]>) -> std::io::Result<String> {
     let mut stream = UnixStream::connect(socket_path)?;
-    stream.write_all(message.as_bytes())?;
+    let cipher = session_key.map(make_cipher);
 
-    let mut buffer = [0; 1024];
+    match &cipher {
+        Some(cipher) => {
+            let framed = encrypt_frame(cipher, message.as_bytes());
+            stream.write_all(&framed)?;
+        }
+        None => stream.write_all(message.as_bytes())?,
+    }
+
+    let mut buffer = [0; 4096];
     let bytes_read = stream.read(&mut buffer)?;
-    let response = String::from_utf8_lossy(&buffer[..bytes_read]).to_string();
-    Ok(response)
+
+    let response = match &cipher {
+        Some(cipher) => decrypt_frame(cipher, trim_delimiter(&buffer[..bytes_read]))?,
+        None => buffer[..bytes_read].to_vec(),
+    };
+    Ok(String::from_utf8_lossy(&response).to_string())
+}
+
+fn make_cipher(session_key: &[u8; SESSION_KEY_LEN]) -> Aes256Gcm {
+    let key = Key::<Aes256Gcm>::from_slice(session_key);
+    Aes256Gcm::new(key)
+}
+
+fn trim_delimiter(framed: &[u8]) -> &[u8] {
+    match framed.last() {
+        Some(&MESSAGE_DELIMITER) => &framed[..framed.len() - 1],
+        _ => framed,
+    }
+}
+
+/// Seals `plaintext` under `cipher` with a fresh random nonce and returns
+/// `base64(nonce || ciphertext+tag)` terminated by `MESSAGE_DELIMITER`.
+fn encrypt_frame(cipher: &Aes256Gcm, plaintext: &[u8]) -> Vec<u8> {
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+    let ciphertext = cipher.encrypt(nonce, plaintext).expect("encryption failure!");
+
+    let mut sealed = Vec::with_capacity(nonce_bytes.len() + ciphertext.len());
+    sealed.extend_from_slice(&nonce_bytes);
+    sealed.extend_from_slice(&ciphertext);
+
+    let mut framed = general_purpose::STANDARD.encode(sealed).into_bytes();
+    framed.push(MESSAGE_DELIMITER);
+    framed
+}
+
+/// Reverses `encrypt_frame`: decodes the base64 frame, splits off the nonce,
+/// and opens the AES-GCM ciphertext.
+fn decrypt_frame(cipher: &Aes256Gcm, framed_base64: &[u8]) -> std::io::Result<Vec<u8>> {
+    let sealed = general_purpose::STANDARD
+        .decode(framed_base64)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    if sealed.len() < 12 {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "encrypted frame too short"));
+    }
+    let (nonce_bytes, ciphertext) = sealed.split_at(12);
+    let nonce = Nonce::from_slice(nonce_bytes);
+    cipher
+        .decrypt(nonce, ciphertext)
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "decryption failed"))
 }
 
 #[cfg(test)]
@@ -67,7 +165,7 @@ mod tests {
 
         // Run server in a separate thread in single-shot mode.
         let server_thread = thread::spawn(move || {
-            run_server(test_socket_path, true).unwrap();
+            run_server(test_socket_path, true, None).unwrap();
         });
 
         // Give the server a moment to start up.
@@ -75,11 +173,30 @@ mod tests {
 
         // Run client.
         let message = "Hello from test client!";
-        let response = run_client(test_socket_path, message).unwrap();
+        let response = run_client(test_socket_path, message, None).unwrap();
 
         assert_eq!(response, "!tneilc tset morf olleH");
 
         // Wait for the server thread to finish.
         server_thread.join().unwrap();
     }
+
+    #[test]
+    fn test_client_server_communication_encrypted() {
+        let test_socket_path = "/tmp/test_socket_encrypted.sock";
+        let session_key = [7u8; SESSION_KEY_LEN];
+
+        let server_thread = thread::spawn(move || {
+            run_server(test_socket_path, true, Some(&session_key)).unwrap();
+        });
+
+        thread::sleep(Duration::from_millis(100));
+
+        let message = "Hello from encrypted test client!";
+        let response = run_client(test_socket_path, message, Some(&session_key)).unwrap();
+
+        assert_eq!(response, message.chars().rev().collect::<String>());
+
+        server_thread.join().unwrap();
+    }
 }
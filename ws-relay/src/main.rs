@@ -1,32 +1,442 @@
-use futures_util::{SinkExt, stream::StreamExt};
+use clap::Parser;
+use futures_util::{
+    SinkExt,
+    stream::{SplitSink, SplitStream, StreamExt},
+};
 use log::{error, info, warn}; // import warn
-use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use nix::pty::{OpenptyResult, Winsize, openpty};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    path::PathBuf,
+    process::Stdio,
+    sync::Arc,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+use subtle::ConstantTimeEq;
 use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, unix::AsyncFd},
     net::{TcpListener, TcpStream},
-    sync::{Mutex, oneshot},
+    sync::{Mutex, broadcast, mpsc, oneshot},
 };
-use tokio_tungstenite::accept_hdr_async;
+use tokio_rustls::TlsAcceptor;
+use tokio_tungstenite::{accept_hdr_async, tungstenite::Message};
+
+/// Generic over the underlying byte stream so the same relay logic serves
+/// both plain `ws://` (`TcpStream`) and `wss://` (`tokio_rustls::server::TlsStream<TcpStream>`).
+type WebSocketStream<S> = tokio_tungstenite::WebSocketStream<S>;
+type WsSink<S> = SplitSink<WebSocketStream<S>, Message>;
+type WsSource<S> = SplitStream<WebSocketStream<S>>;
+
+/// A room pairs one host with any number of clients under the same
+/// `session_id`: the host's messages fan out to every client over
+/// `host_tx` (`broadcast`), and client messages merge up to the host over
+/// `client_tx` (`mpsc`). Channels carry `Message`, not sockets, so a single
+/// room table serves both the plaintext and TLS listeners.
+struct Room {
+    host_tx: broadcast::Sender<Message>,
+    client_tx: mpsc::UnboundedSender<Message>,
+    client_count: Arc<AtomicUsize>,
+    signal_tx: mpsc::UnboundedSender<SignalRequest>,
+    /// Hands out the `client_id` each joining client tags its `SignalRequest`
+    /// with, so the host can tell concurrent joiners' `Signal::Peer` frames
+    /// apart on its single socket.
+    next_client_id: Arc<AtomicU64>,
+    /// Secret the host supplied during authentication, if any; joining
+    /// clients must present the same value in their own `AuthMessage`.
+    session_secret: Option<String>,
+}
+
+type SharedState = Arc<Mutex<HashMap<String, Room>>>;
+
+/// How long to wait for the other side of a room to answer the initial
+/// candidate exchange before giving up and relying on the relay alone.
+const SIGNAL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Rendezvous control messages exchanged over text frames before the regular
+/// data relay takes over. A peer that wants to attempt a direct connection
+/// sends `Candidates` right after the role/session handshake with its
+/// self-reported local endpoints; each side then receives the other's
+/// `Candidates` plus its server-observed public address as a `Peer` message,
+/// probes them directly over UDP (outside this relay), and sends `Connected`
+/// once a probe round-trip succeeds so the relay stops forwarding for it.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Signal {
+    Candidates { candidates: Vec<SocketAddr> },
+    /// `client_id` identifies which joining client this exchange belongs to,
+    /// so a host can correlate `Peer` frames when more than one client joins
+    /// within the signal window.
+    Peer { client_id: u64, candidates: Vec<SocketAddr>, observed_addr: SocketAddr },
+    Connected,
+}
+
+/// A client's candidate exchange request, carried from its connection task
+/// to the host's over the room's `signal_tx`/`signal_rx` channel.
+struct SignalRequest {
+    client_id: u64,
+    candidates: Vec<SocketAddr>,
+    observed_addr: SocketAddr,
+    reply_tx: oneshot::Sender<Signal>,
+}
+
+/// Waits up to `SIGNAL_TIMEOUT` for a `Candidates` message on `ws_stream`.
+/// Returns an empty list (which naturally falls back to plain relaying) on
+/// timeout or disconnect. A peer that doesn't speak the signaling protocol
+/// sends its first relay message instead of a `Candidates` signal; since
+/// that message has already been consumed off the socket, it's handed back
+/// as the second return value so the caller can replay it as the first
+/// message of the normal relay loop instead of dropping it.
+async fn read_candidates<S>(ws_stream: &mut WebSocketStream<S>, peer_addr: SocketAddr) -> (Vec<SocketAddr>, Option<Message>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    match tokio::time::timeout(SIGNAL_TIMEOUT, ws_stream.next()).await {
+        Ok(Some(Ok(Message::Text(text)))) => match serde_json::from_str::<Signal>(&text) {
+            Ok(Signal::Candidates { candidates }) => (candidates, None),
+            _ => (Vec::new(), Some(Message::Text(text))),
+        },
+        Ok(Some(Ok(msg))) => (Vec::new(), Some(msg)),
+        _ => {
+            warn!("No candidate exchange from {}, falling back to relay-only.", peer_addr);
+            (Vec::new(), None)
+        }
+    }
+}
+
+/// True if `text` decodes as a `Signal::Connected` control message.
+fn is_connected_signal(text: &str) -> bool {
+    matches!(serde_json::from_str::<Signal>(text), Ok(Signal::Connected))
+}
+
+/// First message a role=`exec`/`shell` peer sends: describes the process to
+/// spawn and whether it should get a PTY.
+#[derive(Debug, Deserialize)]
+struct Cmd {
+    argv: Vec<String>,
+    #[serde(default)]
+    env: Vec<(String, String)>,
+    #[serde(default)]
+    tty: bool,
+    #[serde(default)]
+    rows: u16,
+    #[serde(default)]
+    cols: u16,
+}
+
+/// Control messages an exec peer sends over text frames once the process is
+/// running; raw process I/O travels over binary frames instead.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ExecControl {
+    Resize { rows: u16, cols: u16 },
+}
+
+/// Final message sent back to an exec peer before the socket closes.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ExecReply {
+    Exit { code: i32 },
+}
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Address to bind the WebSocket relay listener on.
+    #[arg(long, default_value = "0.0.0.0:8765")]
+    addr: String,
+
+    /// Address to bind the QUIC relay listener on.
+    #[arg(long, default_value = "0.0.0.0:8766")]
+    quic_addr: String,
+
+    /// PEM-encoded TLS certificate chain. When set together with `--tls-key`,
+    /// the WebSocket listener speaks `wss://` instead of `ws://`.
+    #[arg(long)]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM-encoded TLS private key, used together with `--tls-cert`.
+    #[arg(long)]
+    tls_key: Option<PathBuf>,
+
+    /// Require every peer to authenticate (bearer token and/or PAM, plus a
+    /// matching per-session secret) before it can join a session. Off by
+    /// default so anonymous relaying keeps working in trusted deployments.
+    #[arg(long)]
+    require_auth: bool,
+
+    /// Bearer token peers must present in their first message when
+    /// `--require-auth` is set.
+    #[arg(long)]
+    auth_token: Option<String>,
+
+    /// Allow peers to request the `exec`/`shell` role, which spawns an
+    /// arbitrary process (attacker-controlled argv and env) on this host.
+    /// Off by default: without it, the relay never grants remote code
+    /// execution no matter what a peer's path asks for.
+    #[arg(long)]
+    enable_exec: bool,
+}
+
+/// Configuration for the pre-pairing authentication phase, built once from
+/// `Args` and shared by every connection handler. Also gates the `exec`/
+/// `shell` role, since that role's authorization requirements are the same
+/// shape (checked once at startup, read on every connection).
+struct AuthConfig {
+    required: bool,
+    token: Option<String>,
+    exec_enabled: bool,
+}
+
+/// First message a peer sends when `--require-auth` is set, before the room
+/// handshake proceeds. Accepted if the bearer token matches `AuthConfig::token`
+/// or, when built with the `pam` feature, `username`/`password` pass a PAM
+/// conversation. `session_secret` is opaque to this relay: the host picks one
+/// and every client in its session must echo it back, so two unrelated peers
+/// can't accidentally (or maliciously) land in the same room id.
+#[derive(Debug, Deserialize)]
+struct AuthMessage {
+    #[serde(default)]
+    bearer_token: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    session_secret: String,
+}
+
+/// Private-use WebSocket close code for a failed authentication attempt.
+const AUTH_FAILURE_CODE: u16 = 4001;
+
+/// Compares two secrets in constant time with respect to their contents.
+/// Length is checked (and thus not hidden) first, which is fine for these
+/// short shared credentials.
+fn secrets_match(a: &str, b: &str) -> bool {
+    a.len() == b.len() && bool::from(a.as_bytes().ct_eq(b.as_bytes()))
+}
+
+/// Same as `secrets_match`, lifted over `Option` so a room with no
+/// configured secret only matches a peer that also presented none.
+fn secrets_match_opt(a: &Option<String>, b: &Option<String>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => secrets_match(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Reads and validates the first message of a connection as an `AuthMessage`.
+/// On any failure (malformed message, disconnect, or a credential that
+/// doesn't check out) the socket is closed with `AUTH_FAILURE_CODE` and
+/// `None` is returned; callers must not use `ws_stream` any further in that case.
+async fn authenticate<S>(ws_stream: &mut WebSocketStream<S>, auth: &AuthConfig, peer_addr: SocketAddr) -> Option<AuthMessage>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let auth_msg = match ws_stream.next().await {
+        Some(Ok(Message::Text(text))) => serde_json::from_str::<AuthMessage>(&text).ok(),
+        Some(Ok(Message::Binary(data))) => serde_json::from_slice::<AuthMessage>(&data).ok(),
+        _ => None,
+    };
+
+    let auth_msg = match auth_msg {
+        Some(msg) => msg,
+        None => {
+            warn!("Auth: missing or malformed auth message from {}", peer_addr);
+            close_unauthorized(ws_stream).await;
+            return None;
+        }
+    };
+
+    if !check_auth_message(auth, &auth_msg) {
+        warn!("Auth: invalid credentials from {}", peer_addr);
+        close_unauthorized(ws_stream).await;
+        return None;
+    }
+
+    Some(auth_msg)
+}
+
+/// Checks an `AuthMessage`'s bearer token or PAM credentials against `auth`,
+/// shared by the WebSocket (`authenticate`) and QUIC (`authenticate_quic`)
+/// handshakes.
+fn check_auth_message(auth: &AuthConfig, auth_msg: &AuthMessage) -> bool {
+    let token_ok = match (&auth.token, &auth_msg.bearer_token) {
+        (Some(expected), Some(provided)) => secrets_match(expected, provided),
+        _ => false,
+    };
+    let pam_ok = match (&auth_msg.username, &auth_msg.password) {
+        (Some(username), Some(password)) => authenticate_pam(username, password),
+        _ => false,
+    };
+    token_ok || pam_ok
+}
+
+/// Closes `ws_stream` with `AUTH_FAILURE_CODE` to tell the peer its
+/// authentication attempt was rejected.
+async fn close_unauthorized<S>(ws_stream: &mut WebSocketStream<S>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let frame = tokio_tungstenite::tungstenite::protocol::CloseFrame {
+        code: tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode::Library(AUTH_FAILURE_CODE),
+        reason: "unauthorized".into(),
+    };
+    let _ = ws_stream.close(Some(frame)).await;
+}
+
+/// Runs a PAM conversation for `username`/`password` when built with the
+/// `pam` feature.
+#[cfg(feature = "pam")]
+fn authenticate_pam(username: &str, password: &str) -> bool {
+    use pam::Authenticator;
+
+    let mut authenticator = match Authenticator::with_password("ws-relay") {
+        Ok(a) => a,
+        Err(e) => {
+            error!("Auth: failed to initialize PAM: {}", e);
+            return false;
+        }
+    };
+    authenticator
+        .get_handler()
+        .set_credentials(username, password);
+    authenticator.authenticate().is_ok()
+}
+
+/// Stub used when the relay is built without the `pam` feature: PAM login
+/// never succeeds, so only the bearer token path can authenticate peers.
+#[cfg(not(feature = "pam"))]
+fn authenticate_pam(_username: &str, _password: &str) -> bool {
+    false
+}
 
-// The following type aliases remain unchanged
-type WebSocketStream = tokio_tungstenite::WebSocketStream<TcpStream>;
-type PeerTx = oneshot::Sender<WebSocketStream>;
-type SharedState = Arc<Mutex<HashMap<String, PeerTx>>>;
+/// ALPN token QUIC clients/servers negotiate for the relay protocol.
+const QUIC_ALPN: &[u8] = b"ws-relay";
+
+/// Pairing table for the QUIC transport, kept separate from the WebSocket
+/// `SharedState` since the two transports run side by side: the oneshot
+/// carries the peer's `quinn::Connection` instead of a `WebSocketStream`.
+type QuicPeerTx = oneshot::Sender<quinn::Connection>;
+
+/// A pending QUIC host: its connection handoff channel plus the session
+/// secret (if any) it authenticated with, which a joining client's own
+/// secret must match, mirroring `Room::session_secret` on the WebSocket side.
+struct QuicPendingHost {
+    peer_tx: QuicPeerTx,
+    session_secret: Option<String>,
+}
+type QuicSharedState = Arc<Mutex<HashMap<String, QuicPendingHost>>>;
 
 #[tokio::main]
 async fn main() {
     env_logger::init();
-    let addr = "0.0.0.0:8765";
+    let args = Args::parse();
+
+    let auth = Arc::new(AuthConfig {
+        required: args.require_auth,
+        token: args.auth_token,
+        exec_enabled: args.enable_exec,
+    });
+    if auth.required {
+        info!("Pre-pairing authentication is required for every peer.");
+    }
+    if auth.exec_enabled {
+        if auth.required {
+            info!("exec/shell role is enabled, gated behind required authentication.");
+        } else {
+            warn!(
+                "exec/shell role is enabled WITHOUT --require-auth: any peer that can reach \
+                 this listener can run arbitrary commands as this process's user."
+            );
+        }
+    }
+
+    let quic_addr: SocketAddr = args.quic_addr.parse().expect("Failed to parse QUIC address");
+    let quic_state = QuicSharedState::new(Mutex::new(HashMap::new()));
+    let quic_auth = auth.clone();
+    tokio::spawn(async move {
+        if let Err(e) = run_quic_relay(quic_state, quic_addr, quic_auth).await {
+            error!("QUIC relay failed: {}", e);
+        }
+    });
+
+    let addr: SocketAddr = args.addr.parse().expect("Failed to parse WebSocket relay address");
+    let state = SharedState::new(Mutex::new(HashMap::new()));
+
+    match (args.tls_cert, args.tls_key) {
+        (Some(cert_path), Some(key_path)) => {
+            let acceptor =
+                build_tls_acceptor(&cert_path, &key_path).expect("Failed to build TLS acceptor");
+            info!("WebSocket Relay server started at: wss://{}", addr);
+            run_ws_relay_tls(addr, acceptor, state, auth).await;
+        }
+        (None, None) => {
+            info!("WebSocket Relay server started at: ws://{}", addr);
+            run_ws_relay_plain(addr, state, auth).await;
+        }
+        _ => {
+            error!("--tls-cert and --tls-key must be provided together");
+        }
+    }
+}
+
+/// Loads a PEM cert chain and private key and builds a `tokio_rustls::TlsAcceptor`
+/// for the `wss://` listener.
+fn build_tls_acceptor(
+    cert_path: &PathBuf,
+    key_path: &PathBuf,
+) -> Result<TlsAcceptor, Box<dyn std::error::Error>> {
+    let cert_file = std::fs::File::open(cert_path)?;
+    let certs = rustls_pemfile::certs(&mut std::io::BufReader::new(cert_file))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let key_file = std::fs::File::open(key_path)?;
+    let key = rustls_pemfile::private_key(&mut std::io::BufReader::new(key_file))?
+        .ok_or("no private key found in --tls-key file")?;
+
+    let tls_config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+
+    Ok(TlsAcceptor::from(Arc::new(tls_config)))
+}
+
+async fn run_ws_relay_plain(addr: SocketAddr, state: SharedState, auth: Arc<AuthConfig>) {
     let listener = TcpListener::bind(&addr)
         .await
         .expect("Failed to bind to address");
-    info!("WebSocket Relay server started at: {}", addr);
-    let state = SharedState::new(Mutex::new(HashMap::new()));
+
+    while let Ok((stream, peer_addr)) = listener.accept().await {
+        tokio::spawn(handle_connection(state.clone(), stream, peer_addr, auth.clone()));
+    }
+}
+
+async fn run_ws_relay_tls(addr: SocketAddr, acceptor: TlsAcceptor, state: SharedState, auth: Arc<AuthConfig>) {
+    let listener = TcpListener::bind(&addr)
+        .await
+        .expect("Failed to bind to address");
+
     while let Ok((stream, peer_addr)) = listener.accept().await {
-        tokio::spawn(handle_connection(state.clone(), stream, peer_addr));
+        let acceptor = acceptor.clone();
+        let state = state.clone();
+        let auth = auth.clone();
+        tokio::spawn(async move {
+            match acceptor.accept(stream).await {
+                Ok(tls_stream) => handle_connection(state, tls_stream, peer_addr, auth).await,
+                Err(e) => error!("TLS handshake failed: {}, from: {}", e, peer_addr),
+            }
+        });
     }
 }
 
-async fn handle_connection(state: SharedState, stream: TcpStream, peer_addr: SocketAddr) {
+async fn handle_connection<S>(state: SharedState, stream: S, peer_addr: SocketAddr, auth: Arc<AuthConfig>)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
     let mut path_from_req = None;
     let callback =
         |req: &tokio_tungstenite::tungstenite::handshake::server::Request,
@@ -52,80 +462,129 @@ async fn handle_connection(state: SharedState, stream: TcpStream, peer_addr: Soc
     };
 
     // --- Start of main logic modification ---
-    // Parse the path, format should be "/[role]/[session_id]"
-    let parts: Vec<&str> = path.trim_matches('/').split('/').collect();
-    if parts.len() != 2 {
-        warn!(
-            "Invalid path format: '{}', from: {}. Should be /[role]/[session_id]",
-            path, peer_addr
-        );
-        return;
-    }
-    let role = parts[0];
-    let session_id = parts[1].to_string();
+    let (role, session_id) = match parse_role_session(&path) {
+        Some(parsed) => parsed,
+        None => {
+            warn!(
+                "Invalid path format: '{}', from: {}. Should be /[role]/[session_id]",
+                path, peer_addr
+            );
+            return;
+        }
+    };
+    let role = role.as_str();
 
     info!(
         "New connection request: role='{}', SessionID='{}', from: {}",
         role, session_id, peer_addr
     );
 
+    let auth_msg = if auth.required {
+        match authenticate(&mut ws_stream, &auth, peer_addr).await {
+            Some(msg) => Some(msg),
+            None => return, // authenticate() has already closed the socket
+        }
+    } else {
+        None
+    };
+
     if role == "host" {
-        // This is the connection logic for Host (B)
-        let mut pending_hosts = state.lock().await;
+        // The host creates the room; any number of clients may join it
+        // afterwards, so its departure is the only thing that tears it down.
+        let mut rooms = state.lock().await;
 
-        if pending_hosts.contains_key(&session_id) {
+        if rooms.contains_key(&session_id) {
             warn!(
                 "Host tried to connect to an already occupied Session ID: '{}'",
                 session_id
             );
-            // Can choose to disconnect this connection or notify the other party
             return;
         }
 
-        info!(
-            "Host '{}' is waiting for a Client connection...",
-            session_id
+        // Give the host a chance to offer hole-punch candidates before the
+        // room is visible to clients; absent or malformed input just yields
+        // an empty list, so rendezvous is opt-in and non-breaking.
+        let (host_candidates, pending_msg) = read_candidates(&mut ws_stream, peer_addr).await;
+
+        info!("Host '{}' is now serving the room...", session_id);
+        let (host_tx, _) = broadcast::channel(256);
+        let (client_tx, client_rx) = mpsc::unbounded_channel();
+        let (signal_tx, signal_rx) = mpsc::unbounded_channel();
+        let client_count = Arc::new(AtomicUsize::new(0));
+        rooms.insert(
+            session_id.clone(),
+            Room {
+                host_tx: host_tx.clone(),
+                client_tx,
+                client_count: client_count.clone(),
+                signal_tx,
+                next_client_id: Arc::new(AtomicU64::new(0)),
+                session_secret: auth_msg.map(|m| m.session_secret),
+            },
         );
-        let (peer_tx, peer_rx) = oneshot::channel();
-        pending_hosts.insert(session_id.clone(), peer_tx);
-        drop(pending_hosts);
+        drop(rooms);
 
-        match peer_rx.await {
-            Ok(peer_ws) => {
-                info!(
-                    "Client connected to '{}', pairing successful, starting data forwarding.",
-                    session_id
-                );
-                forward_streams(ws_stream, peer_ws).await; // Note the parameter order, ws_stream is the host
-                info!("Forwarding for Session '{}' has ended.", session_id);
-            }
-            Err(_) => {
-                // If an error occurs before waiting for the Client, clean up its own record
-                let mut pending_hosts = state.lock().await;
-                pending_hosts.remove(&session_id);
-                drop(pending_hosts);
-                info!(
-                    "Host '{}' disconnected or an error occurred while waiting, cleaned up.",
-                    session_id
-                );
-            }
-        }
+        run_host_room(
+            ws_stream,
+            host_tx,
+            client_rx,
+            signal_rx,
+            host_candidates,
+            pending_msg,
+            peer_addr,
+            &client_count,
+            &session_id,
+        )
+        .await;
+
+        state.lock().await.remove(&session_id);
+        info!("Host '{}' left, room closed.", session_id);
     } else if role == "client" {
-        // This is the connection logic for Client (C)
-        let mut pending_hosts = state.lock().await;
+        // Attach to the live room, whether it was just created or has been
+        // running for a while (late joiners are always welcome).
+        let rooms = state.lock().await;
 
-        if let Some(peer_tx) = pending_hosts.remove(&session_id) {
-            // Found the waiting Host, pairing successful
-            info!(
-                "Client found the waiting Host '{}', proceeding with pairing.",
-                session_id
-            );
-            if peer_tx.send(ws_stream).is_err() {
-                error!(
-                    "Could not send Client connection to Host, maybe the Host just disconnected. Session ID: '{}'",
-                    session_id
-                );
+        if let Some(room) = rooms.get(&session_id) {
+            if auth.required && !secrets_match_opt(&room.session_secret, &auth_msg.as_ref().map(|m| m.session_secret.clone())) {
+                warn!("Auth: session secret mismatch for '{}' from {}", session_id, peer_addr);
+                drop(rooms);
+                close_unauthorized(&mut ws_stream).await;
+                return;
             }
+
+            let host_rx = room.host_tx.subscribe();
+            let client_tx = room.client_tx.clone();
+            let client_count = room.client_count.clone();
+            let signal_tx = room.signal_tx.clone();
+            let next_client_id = room.next_client_id.clone();
+            drop(rooms);
+
+            client_count.fetch_add(1, Ordering::SeqCst);
+            info!("Client joined room '{}'.", session_id);
+
+            let (candidates, pending_msg) = read_candidates(&mut ws_stream, peer_addr).await;
+            // Only a client that actually opted in by sending `Candidates`
+            // takes part in the signal exchange; anything else (including a
+            // plain relay message captured as `pending_msg`) must not cause
+            // an unsolicited `Peer` control frame to land on either socket.
+            if !candidates.is_empty() {
+                let client_id = next_client_id.fetch_add(1, Ordering::SeqCst);
+                let (reply_tx, reply_rx) = oneshot::channel();
+                if signal_tx
+                    .send(SignalRequest { client_id, candidates, observed_addr: peer_addr, reply_tx })
+                    .is_ok()
+                {
+                    if let Ok(Ok(peer_signal)) = tokio::time::timeout(SIGNAL_TIMEOUT, reply_rx).await {
+                        if let Ok(text) = serde_json::to_string(&peer_signal) {
+                            let _ = ws_stream.send(Message::Text(text)).await;
+                        }
+                    }
+                }
+            }
+
+            run_client_room(ws_stream, host_rx, client_tx, pending_msg).await;
+            client_count.fetch_sub(1, Ordering::SeqCst);
+            info!("Client left room '{}'.", session_id);
         } else {
             // Did not find the corresponding Host
             warn!(
@@ -135,32 +594,684 @@ async fn handle_connection(state: SharedState, stream: TcpStream, peer_addr: Soc
             // Disconnect this Client connection directly
             let _ = ws_stream.close(None).await;
         }
+    } else if role == "exec" || role == "shell" {
+        if !auth.exec_enabled {
+            warn!("exec/shell role requested from {} but --enable-exec is not set; refusing.", peer_addr);
+            let _ = ws_stream.close(None).await;
+            return;
+        }
+        // The peer itself drives a spawned process rather than being paired
+        // with another relay peer.
+        handle_exec_connection(ws_stream, peer_addr).await;
     } else {
         warn!("Unknown role: '{}', from: {}", role, peer_addr);
     }
     // --- End of main logic modification ---
 }
 
-// The forward_streams function remains unchanged, its design already ensures synchronous disconnection
-async fn forward_streams(ws1: WebSocketStream, ws2: WebSocketStream) {
-    let (mut write1, mut read1) = ws1.split();
-    let (mut write2, mut read2) = ws2.split();
+/// Parses a relay path of the form "/[role]/[session_id]", shared by the
+/// WebSocket and QUIC transports.
+fn parse_role_session(path: &str) -> Option<(String, String)> {
+    let parts: Vec<&str> = path.trim_matches('/').split('/').collect();
+    if parts.len() != 2 {
+        return None;
+    }
+    Some((parts[0].to_string(), parts[1].to_string()))
+}
+
+/// Drives a room's host: fans every host message out to all subscribed
+/// clients over `host_tx`, and forwards every client message merged onto
+/// `client_rx` back down to the host. Returning drops `host_tx`, which closes
+/// every client's `broadcast::Receiver` and disconnects them.
+async fn run_host_room<S>(
+    ws_stream: WebSocketStream<S>,
+    host_tx: broadcast::Sender<Message>,
+    mut client_rx: mpsc::UnboundedReceiver<Message>,
+    mut signal_rx: mpsc::UnboundedReceiver<SignalRequest>,
+    host_candidates: Vec<SocketAddr>,
+    pending_msg: Option<Message>,
+    host_addr: SocketAddr,
+    client_count: &AtomicUsize,
+    session_id: &str,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut write, mut read) = ws_stream.split();
+
+    // `read_candidates` already consumed this message off the socket while
+    // waiting for a signal; a peer that isn't rendezvous-aware sent its
+    // first real relay message here instead, so hand it to `host_tx` like
+    // any other host message before entering the loop. The room was only
+    // just published, so usually no client has subscribed yet; the broadcast
+    // then has no receiver and the message is discarded.
+    if let Some(msg) = pending_msg {
+        let _ = host_tx.send(msg);
+    }
 
     loop {
         tokio::select! {
-            Some(Ok(msg)) = read1.next() => {
-                if write2.send(msg).await.is_err() {
-                    break;
+            msg = read.next() => {
+                match msg {
+                    Some(Ok(Message::Text(text))) if is_connected_signal(&text) => {
+                        info!("Room '{}' host promoted to a direct path, stopping relay.", session_id);
+                        break;
+                    }
+                    Some(Ok(msg)) => {
+                        // No error if there are currently no subscribed clients.
+                        let _ = host_tx.send(msg);
+                    }
+                    _ => break,
                 }
             }
-            Some(Ok(msg)) = read2.next() => {
-                if write1.send(msg).await.is_err() {
+            Some(msg) = client_rx.recv() => {
+                if write.send(msg).await.is_err() {
                     break;
                 }
             }
-            else => {
-                break;
+            // A newly joined client wants to exchange hole-punch candidates
+            // with the host; hand the host's side straight to its socket and
+            // the client's side back through the oneshot.
+            Some(req) = signal_rx.recv() => {
+                let peer_signal = Signal::Peer {
+                    client_id: req.client_id,
+                    candidates: req.candidates,
+                    observed_addr: req.observed_addr,
+                };
+                if let Ok(text) = serde_json::to_string(&peer_signal) {
+                    let _ = write.send(Message::Text(text)).await;
+                }
+                let _ = req.reply_tx.send(Signal::Peer {
+                    client_id: req.client_id,
+                    candidates: host_candidates.clone(),
+                    observed_addr: host_addr,
+                });
+            }
+            else => break,
+        }
+    }
+
+    info!(
+        "Room '{}' host disconnected with {} client(s) still attached.",
+        session_id,
+        client_count.load(Ordering::SeqCst)
+    );
+}
+
+/// Drives a single client within a room: relays its messages up to the host
+/// over `client_tx`, and plays back whatever it receives from `host_rx`.
+async fn run_client_room<S>(
+    ws_stream: WebSocketStream<S>,
+    mut host_rx: broadcast::Receiver<Message>,
+    client_tx: mpsc::UnboundedSender<Message>,
+    pending_msg: Option<Message>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut write, mut read) = ws_stream.split();
+
+    // Same replay as `run_host_room`: a non-rendezvous-aware client's first
+    // relay message was consumed by `read_candidates` and must still reach
+    // the host.
+    if let Some(msg) = pending_msg {
+        if client_tx.send(msg).is_err() {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            msg = read.next() => {
+                match msg {
+                    Some(Ok(Message::Text(text))) if is_connected_signal(&text) => {
+                        info!("Client promoted to a direct path, stopping relay.");
+                        break;
+                    }
+                    Some(Ok(msg)) => {
+                        if client_tx.send(msg).is_err() {
+                            break;
+                        }
+                    }
+                    _ => break,
+                }
+            }
+            result = host_rx.recv() => {
+                match result {
+                    Ok(msg) => {
+                        if write.send(msg).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                }
+            }
+        }
+    }
+}
+
+/// Handles a role=`exec`/`shell` peer: reads the `Cmd` handshake, spawns the
+/// requested process, and splices its I/O with the WebSocket until it exits
+/// or the peer disconnects.
+async fn handle_exec_connection<S>(ws_stream: WebSocketStream<S>, peer_addr: SocketAddr)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut write, mut read) = ws_stream.split();
+
+    let cmd: Cmd = match read.next().await {
+        Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+            Ok(cmd) => cmd,
+            Err(e) => {
+                error!("exec: invalid Cmd from {}: {}", peer_addr, e);
+                return;
+            }
+        },
+        Some(Ok(Message::Binary(data))) => match serde_json::from_slice(&data) {
+            Ok(cmd) => cmd,
+            Err(e) => {
+                error!("exec: invalid Cmd from {}: {}", peer_addr, e);
+                return;
+            }
+        },
+        _ => {
+            error!("exec: connection from {} closed before sending a Cmd", peer_addr);
+            return;
+        }
+    };
+
+    if cmd.argv.is_empty() {
+        error!("exec: empty argv from {}", peer_addr);
+        return;
+    }
+
+    info!(
+        "exec: spawning '{}' for {} (tty={})",
+        cmd.argv.join(" "),
+        peer_addr,
+        cmd.tty
+    );
+
+    let exit_code = if cmd.tty {
+        run_exec_tty(&cmd, &mut write, &mut read, peer_addr).await
+    } else {
+        run_exec_pipes(&cmd, &mut write, &mut read, peer_addr).await
+    };
+
+    if let Ok(reply) = serde_json::to_string(&ExecReply::Exit { code: exit_code }) {
+        let _ = write.send(Message::Text(reply)).await;
+    }
+    let _ = write.close().await;
+}
+
+/// Runs `cmd` behind a freshly allocated PTY, relaying the master fd's bytes
+/// as binary WebSocket frames and applying `Resize` control messages as
+/// `TIOCSWINSZ` ioctls on the PTY.
+async fn run_exec_tty<S>(cmd: &Cmd, write: &mut WsSink<S>, read: &mut WsSource<S>, peer_addr: SocketAddr) -> i32
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let winsize = Winsize {
+        ws_row: cmd.rows.max(1),
+        ws_col: cmd.cols.max(1),
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    let OpenptyResult { master, slave } = match openpty(Some(&winsize), None) {
+        Ok(pty) => pty,
+        Err(e) => {
+            error!("exec: openpty failed for {}: {}", peer_addr, e);
+            return -1;
+        }
+    };
+
+    let mut child = match spawn_pty_child(cmd, &slave) {
+        Ok(child) => child,
+        Err(e) => {
+            error!("exec: failed to spawn '{}' for {}: {}", cmd.argv[0], peer_addr, e);
+            return -1;
+        }
+    };
+    drop(slave); // the child holds its own copies of the slave fd
+
+    let pty = match AsyncPty::new(master) {
+        Ok(pty) => pty,
+        Err(e) => {
+            error!("exec: failed to wrap PTY master for {}: {}", peer_addr, e);
+            let _ = child.start_kill();
+            return -1;
+        }
+    };
+
+    let mut buf = [0u8; 4096];
+    loop {
+        tokio::select! {
+            result = pty.read(&mut buf) => {
+                match result {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        if write.send(Message::Binary(buf[..n].to_vec())).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+            msg = read.next() => {
+                match msg {
+                    Some(Ok(Message::Binary(data))) => {
+                        if pty.write(&data).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Text(text))) => {
+                        match serde_json::from_str::<ExecControl>(&text) {
+                            Ok(ExecControl::Resize { rows, cols }) => pty.resize(rows.max(1), cols.max(1)),
+                            Err(e) => warn!("exec: invalid control message from {}: {}", peer_addr, e),
+                        }
+                    }
+                    Some(Ok(_)) => {}
+                    _ => break,
+                }
+            }
+            status = child.wait() => {
+                return status.ok().and_then(|s| s.code()).unwrap_or(-1);
+            }
+        }
+    }
+
+    let _ = child.start_kill();
+    child.wait().await.ok().and_then(|s| s.code()).unwrap_or(-1)
+}
+
+/// Spawns `cmd` with `slave` as its controlling terminal: the child gets its
+/// own session via `setsid` and claims the PTY with `TIOCSCTTY` before exec.
+fn spawn_pty_child(cmd: &Cmd, slave: &OwnedFd) -> std::io::Result<tokio::process::Child> {
+    let slave_fd = slave.as_raw_fd();
+    let mut command = tokio::process::Command::new(&cmd.argv[0]);
+    command
+        .args(&cmd.argv[1..])
+        .envs(cmd.env.iter().cloned())
+        .stdin(Stdio::from(dup_slave(slave_fd)?))
+        .stdout(Stdio::from(dup_slave(slave_fd)?))
+        .stderr(Stdio::from(dup_slave(slave_fd)?));
+
+    unsafe {
+        command.pre_exec(move || {
+            nix::unistd::setsid()?;
+            if libc::ioctl(slave_fd, libc::TIOCSCTTY as _, 0) < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    command.spawn()
+}
+
+/// Duplicates the PTY slave fd so the child's stdin/stdout/stderr can each
+/// own an independent copy.
+fn dup_slave(fd: std::os::fd::RawFd) -> std::io::Result<OwnedFd> {
+    let dup_fd = nix::unistd::dup(fd)?;
+    Ok(unsafe { OwnedFd::from_raw_fd(dup_fd) })
+}
+
+/// Minimal readiness-based async wrapper around a PTY master fd; tokio has no
+/// built-in async file type for character devices.
+struct AsyncPty {
+    inner: AsyncFd<OwnedFd>,
+}
+
+impl AsyncPty {
+    fn new(fd: OwnedFd) -> std::io::Result<Self> {
+        let flags = nix::fcntl::fcntl(fd.as_raw_fd(), nix::fcntl::FcntlArg::F_GETFL)?;
+        let mut oflags = nix::fcntl::OFlag::from_bits_truncate(flags);
+        oflags.insert(nix::fcntl::OFlag::O_NONBLOCK);
+        nix::fcntl::fcntl(fd.as_raw_fd(), nix::fcntl::FcntlArg::F_SETFL(oflags))?;
+        Ok(Self {
+            inner: AsyncFd::new(fd)?,
+        })
+    }
+
+    fn resize(&self, rows: u16, cols: u16) {
+        let ws = Winsize {
+            ws_row: rows,
+            ws_col: cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        unsafe {
+            libc::ioctl(self.inner.as_raw_fd(), libc::TIOCSWINSZ, &ws);
+        }
+    }
+
+    async fn read(&self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let mut guard = self.inner.readable().await?;
+            match guard.try_io(|fd| nix::unistd::read(fd.as_raw_fd(), buf).map_err(std::io::Error::from)) {
+                Ok(result) => return result,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    async fn write(&self, buf: &[u8]) -> std::io::Result<usize> {
+        loop {
+            let mut guard = self.inner.writable().await?;
+            match guard.try_io(|fd| nix::unistd::write(fd.as_raw_fd(), buf).map_err(std::io::Error::from)) {
+                Ok(result) => return result,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+/// Runs `cmd` with plain stdio pipes (no PTY), used when the peer did not
+/// request a TTY.
+async fn run_exec_pipes<S>(cmd: &Cmd, write: &mut WsSink<S>, read: &mut WsSource<S>, peer_addr: SocketAddr) -> i32
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut child = match tokio::process::Command::new(&cmd.argv[0])
+        .args(&cmd.argv[1..])
+        .envs(cmd.env.iter().cloned())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            error!("exec: failed to spawn '{}' for {}: {}", cmd.argv[0], peer_addr, e);
+            return -1;
+        }
+    };
+
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    let mut stdout = child.stdout.take().expect("piped stdout");
+    let mut stderr = child.stderr.take().expect("piped stderr");
+
+    let mut stdout_buf = [0u8; 4096];
+    let mut stderr_buf = [0u8; 4096];
+    loop {
+        tokio::select! {
+            result = stdout.read(&mut stdout_buf) => {
+                match result {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        if write.send(Message::Binary(stdout_buf[..n].to_vec())).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+            result = stderr.read(&mut stderr_buf) => {
+                match result {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        if write.send(Message::Binary(stderr_buf[..n].to_vec())).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+            msg = read.next() => {
+                match msg {
+                    Some(Ok(Message::Binary(data))) => {
+                        if stdin.write_all(&data).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(_)) => {}
+                    _ => break,
+                }
+            }
+            status = child.wait() => {
+                let _ = child.start_kill();
+                return status.ok().and_then(|s| s.code()).unwrap_or(-1);
             }
         }
     }
+
+    let _ = child.start_kill();
+    child.wait().await.ok().and_then(|s| s.code()).unwrap_or(-1)
+}
+
+/// Builds a `quinn::ServerConfig` for the relay's QUIC listener. Since the
+/// relay has no externally issued certificate, it generates a self-signed one
+/// at startup; the ALPN token is what clients actually authenticate the
+/// protocol with.
+fn build_quic_server_config() -> Result<quinn::ServerConfig, Box<dyn std::error::Error>> {
+    let cert = rcgen::generate_simple_self_signed(vec!["ws-relay".to_string()])?;
+    let cert_der = cert.cert.der().clone();
+    let key_der = rustls::pki_types::PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der());
+
+    let mut tls_config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert_der], key_der.into())?;
+    tls_config.alpn_protocols = vec![QUIC_ALPN.to_vec()];
+
+    let quic_tls_config = quinn::crypto::rustls::QuicServerConfig::try_from(tls_config)?;
+    let mut server_config = quinn::ServerConfig::with_crypto(Arc::new(quic_tls_config));
+    Arc::get_mut(&mut server_config.transport)
+        .expect("fresh ServerConfig transport has no other owners")
+        .max_concurrent_bidi_streams(256u32.into());
+
+    Ok(server_config)
+}
+
+/// Runs the QUIC transport backend for the relay: same `/[role]/[session_id]`
+/// addressing as the WebSocket path, but once paired, every bidirectional
+/// stream a peer opens is spliced independently so concurrent substreams
+/// (control, bulk, resize, ...) don't head-of-line block each other.
+async fn run_quic_relay(state: QuicSharedState, addr: SocketAddr, auth: Arc<AuthConfig>) -> Result<(), Box<dyn std::error::Error>> {
+    let server_config = build_quic_server_config()?;
+    let endpoint = quinn::Endpoint::server(server_config, addr)?;
+    info!("QUIC relay listening at: {} (ALPN: {:?})", addr, String::from_utf8_lossy(QUIC_ALPN));
+
+    while let Some(incoming) = endpoint.accept().await {
+        let state = state.clone();
+        let auth = auth.clone();
+        tokio::spawn(async move {
+            match incoming.await {
+                Ok(connection) => handle_quic_connection(state, connection, auth).await,
+                Err(e) => error!("QUIC handshake failed: {}", e),
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Reads a single `\n`-terminated line (up to 256 bytes) off a QUIC
+/// handshake stream, used for both the role/session line and, when
+/// `--require-auth` is set, the auth-message line that follows it.
+async fn read_quic_line(recv: &mut quinn::RecvStream) -> Option<String> {
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        match recv.read_exact(&mut byte).await {
+            Ok(()) if byte[0] == b'\n' => break,
+            Ok(()) => line.push(byte[0]),
+            Err(_) => return None,
+        }
+        if line.len() > 256 {
+            return None;
+        }
+    }
+    Some(String::from_utf8_lossy(&line).to_string())
+}
+
+/// Reads and validates an `AuthMessage` off the QUIC handshake stream, the
+/// same credential check `authenticate()` runs for the WebSocket path. On
+/// any failure the connection is closed with `AUTH_FAILURE_CODE` and `None`
+/// is returned.
+async fn authenticate_quic(
+    connection: &quinn::Connection,
+    send: &mut quinn::SendStream,
+    recv: &mut quinn::RecvStream,
+    auth: &AuthConfig,
+    peer_addr: SocketAddr,
+) -> Option<AuthMessage> {
+    let auth_msg = match read_quic_line(recv).await.and_then(|line| serde_json::from_str::<AuthMessage>(&line).ok()) {
+        Some(msg) => msg,
+        None => {
+            warn!("QUIC Auth: missing or malformed auth message from {}", peer_addr);
+            let _ = send.write_all(b"unauthorized\n").await;
+            connection.close((AUTH_FAILURE_CODE as u32).into(), b"unauthorized");
+            return None;
+        }
+    };
+
+    if !check_auth_message(auth, &auth_msg) {
+        warn!("QUIC Auth: invalid credentials from {}", peer_addr);
+        let _ = send.write_all(b"unauthorized\n").await;
+        connection.close((AUTH_FAILURE_CODE as u32).into(), b"unauthorized");
+        return None;
+    }
+
+    Some(auth_msg)
+}
+
+async fn handle_quic_connection(state: QuicSharedState, connection: quinn::Connection, auth: Arc<AuthConfig>) {
+    let peer_addr = connection.remote_address();
+
+    let (mut send, mut recv) = match connection.accept_bi().await {
+        Ok(streams) => streams,
+        Err(e) => {
+            error!("QUIC: failed to accept handshake stream from {}: {}", peer_addr, e);
+            return;
+        }
+    };
+
+    // The handshake is a single line carried on the first bidirectional stream:
+    // "/[role]/[session_id]\n"
+    let path = match read_quic_line(&mut recv).await {
+        Some(line) => line,
+        None => {
+            warn!("QUIC: handshake line too long or unreadable from {}", peer_addr);
+            return;
+        }
+    };
+
+    let (role, session_id) = match parse_role_session(&path) {
+        Some(parsed) => parsed,
+        None => {
+            warn!(
+                "QUIC: invalid path format: '{}', from: {}. Should be /[role]/[session_id]",
+                path, peer_addr
+            );
+            return;
+        }
+    };
+
+    info!("QUIC: new connection request: role='{}', SessionID='{}', from: {}", role, session_id, peer_addr);
+
+    let auth_msg = if auth.required {
+        match authenticate_quic(&connection, &mut send, &mut recv, &auth, peer_addr).await {
+            Some(msg) => Some(msg),
+            None => return, // authenticate_quic() has already closed the connection
+        }
+    } else {
+        None
+    };
+    let _ = send.write_all(b"ok\n").await;
+
+    if role == "host" {
+        let mut pending_hosts = state.lock().await;
+        if pending_hosts.contains_key(&session_id) {
+            warn!("QUIC: host tried to connect to an already occupied Session ID: '{}'", session_id);
+            return;
+        }
+        let (peer_tx, peer_rx) = oneshot::channel();
+        pending_hosts.insert(
+            session_id.clone(),
+            QuicPendingHost { peer_tx, session_secret: auth_msg.map(|m| m.session_secret) },
+        );
+        drop(pending_hosts);
+
+        match peer_rx.await {
+            Ok(peer_connection) => {
+                info!("QUIC: client connected to '{}', starting stream forwarding.", session_id);
+                forward_quic_streams(connection, peer_connection).await;
+                info!("QUIC: forwarding for Session '{}' has ended.", session_id);
+            }
+            Err(_) => {
+                state.lock().await.remove(&session_id);
+            }
+        }
+    } else if role == "client" {
+        let mut pending_hosts = state.lock().await;
+        match pending_hosts.get(&session_id) {
+            Some(pending) => {
+                let client_secret = auth_msg.as_ref().map(|m| m.session_secret.clone());
+                if auth.required && !secrets_match_opt(&pending.session_secret, &client_secret) {
+                    warn!("QUIC Auth: session secret mismatch for '{}' from {}", session_id, peer_addr);
+                    drop(pending_hosts);
+                    connection.close((AUTH_FAILURE_CODE as u32).into(), b"unauthorized");
+                    return;
+                }
+                let pending = pending_hosts.remove(&session_id).expect("checked with get() above");
+                if pending.peer_tx.send(connection).is_err() {
+                    error!("QUIC: could not send client connection to host for session '{}'", session_id);
+                }
+            }
+            None => {
+                warn!("QUIC: client tried to connect to unknown Session ID: '{}'", session_id);
+                connection.close(0u32.into(), b"unknown session");
+            }
+        }
+    } else {
+        warn!("QUIC: unknown role '{}' from {}", role, peer_addr);
+    }
+}
+
+/// Once two QUIC connections are paired, relay every subsequent bidirectional
+/// stream either side opens: whenever one side accepts a new stream, open a
+/// matching one on the peer connection and splice the two directions with
+/// `tokio::io::copy`, each pair running as its own task.
+async fn forward_quic_streams(local: quinn::Connection, remote: quinn::Connection) {
+    loop {
+        tokio::select! {
+            accepted = local.accept_bi() => splice_one_quic_stream(accepted, remote.clone(), "local->remote"),
+            accepted = remote.accept_bi() => splice_one_quic_stream(accepted, local.clone(), "remote->local"),
+            else => break,
+        }
+
+        if local.close_reason().is_some() || remote.close_reason().is_some() {
+            break;
+        }
+    }
+}
+
+fn splice_one_quic_stream(
+    accepted: Result<(quinn::SendStream, quinn::RecvStream), quinn::ConnectionError>,
+    peer: quinn::Connection,
+    direction: &'static str,
+) {
+    let (mut accepted_send, mut accepted_recv) = match accepted {
+        Ok(streams) => streams,
+        Err(_) => return,
+    };
+
+    tokio::spawn(async move {
+        let (mut peer_send, mut peer_recv) = match peer.open_bi().await {
+            Ok(streams) => streams,
+            Err(e) => {
+                error!("QUIC ({}): failed to open matching stream: {}", direction, e);
+                return;
+            }
+        };
+
+        tokio::select! {
+            result = tokio::io::copy(&mut accepted_recv, &mut peer_send) => {
+                if let Err(e) = result {
+                    warn!("QUIC ({}): stream copy ended: {}", direction, e);
+                }
+            }
+            result = tokio::io::copy(&mut peer_recv, &mut accepted_send) => {
+                if let Err(e) = result {
+                    warn!("QUIC ({}): stream copy ended: {}", direction, e);
+                }
+            }
+        }
+    });
 }